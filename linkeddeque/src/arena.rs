@@ -0,0 +1,1039 @@
+use itertools::{
+    FoldWhile::{Continue, Done},
+    Itertools,
+};
+
+/// A doubly-linked deque whose nodes all live in a single [`Vec`].
+///
+/// Nodes refer to each other by their `u32` index in the slab rather than by pointer, and slots
+/// freed by removals are kept on a free list to be reused by later insertions. This keeps the O(1)
+/// relinking of [`LinkedDeque`] while only allocating when the slab needs to grow, and keeps nodes
+/// close together in memory.
+///
+/// The one operation this cannot do in O(1) is moving nodes between deques, since they live in
+/// different slabs: [`append`] and [`splice_before`] are O(n) in the length of the other deque.
+///
+/// [`LinkedDeque`]: crate::LinkedDeque
+/// [`append`]: ArenaDeque::append
+/// [`splice_before`]: CursorMut::splice_before
+pub struct ArenaDeque<T> {
+    slab: Vec<Slot<T>>,
+    head: u32,
+    tail: u32,
+    free: u32,
+    size: usize,
+}
+
+/// Stands in for a missing link, since `u32::MAX` can never be a valid index into the slab.
+const NIL: u32 = u32::MAX;
+
+#[derive(Clone)]
+enum Slot<T> {
+    Occupied(Node<T>),
+    /// A free slot, holding the index of the next free slot.
+    Vacant(u32),
+}
+
+#[derive(Clone)]
+struct Node<T> {
+    elem: T,
+    prev: u32,
+    next: u32,
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for ArenaDeque<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone> Clone for ArenaDeque<T> {
+    /// This clones the slab as-is, free slots included, rather than walking the deque.
+    fn clone(&self) -> Self {
+        Self {
+            slab: self.slab.clone(),
+            head: self.head,
+            tail: self.tail,
+            free: self.free,
+            size: self.size,
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for ArenaDeque<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size
+            && self
+                .iter()
+                .zip(other.iter())
+                .fold_while(
+                    true,
+                    |_, (a, b)| {
+                        if a == b { Continue(true) } else { Done(false) }
+                    },
+                )
+                .into_inner()
+    }
+}
+
+impl<T: Eq> Eq for ArenaDeque<T> {}
+
+impl<T: std::hash::Hash> std::hash::Hash for ArenaDeque<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.size.hash(state);
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for ArenaDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Self::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+impl<T> Extend<T> for ArenaDeque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a> Extend<&'a T> for ArenaDeque<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T> Default for ArenaDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ArenaDeque<T> {
+    pub fn new() -> Self {
+        Self {
+            slab: Vec::new(),
+            head: NIL,
+            tail: NIL,
+            free: NIL,
+            size: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slab: Vec::with_capacity(capacity),
+            ..Self::new()
+        }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.link(NIL, self.head, elem);
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.link(self.tail, NIL, elem);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        (self.head != NIL).then(|| self.unlink(self.head).elem)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        (self.tail != NIL).then(|| self.unlink(self.tail).elem)
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        (self.head != NIL).then(|| &self.node(self.head).elem)
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        (self.tail != NIL).then(|| &self.node(self.tail).elem)
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        (self.head != NIL).then(|| &mut self.node_mut(self.head).elem)
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        (self.tail != NIL).then(|| &mut self.node_mut(self.tail).elem)
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// The number of nodes the slab can hold without reallocating, free slots included.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    pub fn clear(&mut self) {
+        self.slab.clear();
+        self.head = NIL;
+        self.tail = NIL;
+        self.free = NIL;
+        self.size = 0;
+    }
+
+    /// Moves all the elements of `other` to the back of the deque, leaving `other` empty. Unlike
+    /// [`LinkedDeque::append`], this is O(n) in the length of `other` since its nodes have to be
+    /// moved into this deque's slab.
+    ///
+    /// [`LinkedDeque::append`]: crate::LinkedDeque::append
+    pub fn append(&mut self, other: &mut Self) {
+        while let Some(elem) = other.pop_front() {
+            self.push_back(elem);
+        }
+        other.clear();
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slab: &self.slab,
+            head: self.head,
+            tail: self.tail,
+            size: self.size,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slab: self.slab.as_mut_ptr(),
+            head: self.head,
+            tail: self.tail,
+            size: self.size,
+            _boo: std::marker::PhantomData,
+        }
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            cur: self.head,
+            index: (self.head != NIL).then_some(0),
+            deque: self,
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            cur: self.tail,
+            index: self.size.checked_sub(1),
+            deque: self,
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            cur: self.head,
+            index: (self.head != NIL).then_some(0),
+            deque: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            cur: self.tail,
+            index: self.size.checked_sub(1),
+            deque: self,
+        }
+    }
+
+    fn node(&self, index: u32) -> &Node<T> {
+        match &self.slab[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant(_) => unreachable!("Link to a free slot"),
+        }
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut Node<T> {
+        match &mut self.slab[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant(_) => unreachable!("Link to a free slot"),
+        }
+    }
+
+    /// Stores `elem` in a new node in between `prev` and `next`, which must be adjacent in the
+    /// deque, reusing a free slot if there is one. Returns the index of the new node.
+    fn link(&mut self, prev: u32, next: u32, elem: T) -> u32 {
+        let node = Slot::Occupied(Node { elem, prev, next });
+
+        let index = if self.free != NIL {
+            let index = self.free;
+            match std::mem::replace(&mut self.slab[index as usize], node) {
+                Slot::Vacant(next_free) => self.free = next_free,
+                Slot::Occupied(_) => unreachable!("Free list points to an occupied slot"),
+            }
+            index
+        } else {
+            assert!(
+                self.slab.len() < NIL as usize,
+                "Cannot add more elements, arena is full"
+            );
+            self.slab.push(node);
+            (self.slab.len() - 1) as u32
+        };
+
+        match prev {
+            NIL => self.head = index,
+            prev => self.node_mut(prev).next = index,
+        }
+        match next {
+            NIL => self.tail = index,
+            next => self.node_mut(next).prev = index,
+        }
+
+        self.size += 1;
+        index
+    }
+
+    /// Removes the node at `index` from the deque and adds its slot to the free list.
+    fn unlink(&mut self, index: u32) -> Node<T> {
+        let node = match std::mem::replace(&mut self.slab[index as usize], Slot::Vacant(self.free))
+        {
+            Slot::Occupied(node) => node,
+            Slot::Vacant(_) => unreachable!("Link to a free slot"),
+        };
+        self.free = index;
+
+        match node.prev {
+            NIL => self.head = node.next,
+            prev => self.node_mut(prev).next = node.next,
+        }
+        match node.next {
+            NIL => self.tail = node.prev,
+            next => self.node_mut(next).prev = node.prev,
+        }
+
+        self.size -= 1;
+        node
+    }
+}
+
+/// A read-only cursor over an [`ArenaDeque`], see [`crate::Cursor`].
+pub struct Cursor<'a, T> {
+    cur: u32,
+    index: Option<usize>,
+    deque: &'a ArenaDeque<T>,
+}
+
+impl<T> Clone for Cursor<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            cur: self.cur,
+            index: self.index,
+            deque: self.deque,
+        }
+    }
+}
+
+impl<'a, T> Cursor<'a, T> {
+    /// The index of the element under the cursor, or [`None`] if it is on the ghost element.
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        match self.cur {
+            NIL => {
+                self.cur = self.deque.head;
+                self.index = (self.cur != NIL).then_some(0);
+            }
+            cur => {
+                self.cur = self.deque.node(cur).next;
+                self.index = self
+                    .index
+                    .filter(|_| self.cur != NIL)
+                    .map(|index| index + 1);
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match self.cur {
+            NIL => {
+                self.cur = self.deque.tail;
+                self.index = self.deque.size.checked_sub(1);
+            }
+            cur => {
+                self.cur = self.deque.node(cur).prev;
+                self.index = self
+                    .index
+                    .filter(|_| self.cur != NIL)
+                    .map(|index| index - 1);
+            }
+        }
+    }
+
+    pub fn current(&self) -> Option<&'a T> {
+        (self.cur != NIL).then(|| &self.deque.node(self.cur).elem)
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        let next = match self.cur {
+            NIL => self.deque.head,
+            cur => self.deque.node(cur).next,
+        };
+        (next != NIL).then(|| &self.deque.node(next).elem)
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        let prev = match self.cur {
+            NIL => self.deque.tail,
+            cur => self.deque.node(cur).prev,
+        };
+        (prev != NIL).then(|| &self.deque.node(prev).elem)
+    }
+}
+
+/// A cursor over an [`ArenaDeque`] which can also edit the deque around its position, see
+/// [`crate::CursorMut`].
+pub struct CursorMut<'a, T> {
+    cur: u32,
+    index: Option<usize>,
+    deque: &'a mut ArenaDeque<T>,
+}
+
+impl<T> CursorMut<'_, T> {
+    /// The index of the element under the cursor, or [`None`] if it is on the ghost element.
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        match self.cur {
+            NIL => {
+                self.cur = self.deque.head;
+                self.index = (self.cur != NIL).then_some(0);
+            }
+            cur => {
+                self.cur = self.deque.node(cur).next;
+                self.index = self
+                    .index
+                    .filter(|_| self.cur != NIL)
+                    .map(|index| index + 1);
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match self.cur {
+            NIL => {
+                self.cur = self.deque.tail;
+                self.index = self.deque.size.checked_sub(1);
+            }
+            cur => {
+                self.cur = self.deque.node(cur).prev;
+                self.index = self
+                    .index
+                    .filter(|_| self.cur != NIL)
+                    .map(|index| index - 1);
+            }
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        (self.cur != NIL).then(|| &mut self.deque.node_mut(self.cur).elem)
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = self.next();
+        (next != NIL).then(|| &mut self.deque.node_mut(next).elem)
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let prev = self.prev();
+        (prev != NIL).then(|| &mut self.deque.node_mut(prev).elem)
+    }
+
+    /// Returns a read-only cursor at the same position, which cannot outlive this one.
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            cur: self.cur,
+            index: self.index,
+            deque: self.deque,
+        }
+    }
+
+    /// Inserts `elem` right before the cursor. If the cursor is on the ghost element, this inserts
+    /// at the back of the deque.
+    pub fn insert_before(&mut self, elem: T) {
+        self.deque.link(self.prev(), self.cur, elem);
+        self.index = self.index.map(|index| index + 1);
+    }
+
+    /// Inserts `elem` right after the cursor. If the cursor is on the ghost element, this inserts
+    /// at the front of the deque.
+    pub fn insert_after(&mut self, elem: T) {
+        self.deque.link(self.cur, self.next(), elem);
+    }
+
+    /// Removes the element under the cursor and moves the cursor to the next element. Returns
+    /// [`None`] if the cursor is on the ghost element.
+    pub fn remove_current(&mut self) -> Option<T> {
+        (self.cur != NIL).then(|| {
+            let node = self.deque.unlink(self.cur);
+            self.cur = node.next;
+            self.index = self.index.filter(|_| self.cur != NIL);
+            node.elem
+        })
+    }
+
+    /// Moves all the elements of `other` right before the cursor, in order. If the cursor is on
+    /// the ghost element, they are appended at the back of the deque. This is O(n) in the length
+    /// of `other`.
+    pub fn splice_before(&mut self, other: ArenaDeque<T>) {
+        let prev = self.prev();
+        let len = other.len();
+
+        other
+            .into_iter()
+            .fold(prev, |prev, elem| self.deque.link(prev, self.cur, elem));
+        self.index = self.index.map(|index| index + len);
+    }
+
+    /// Moves all the elements of `other` right after the cursor, in order. If the cursor is on the
+    /// ghost element, they are prepended at the front of the deque. This is O(n) in the length of
+    /// `other`.
+    pub fn splice_after(&mut self, other: ArenaDeque<T>) {
+        let next = self.next();
+
+        other
+            .into_iter()
+            .rev()
+            .fold(next, |next, elem| self.deque.link(self.cur, next, elem));
+    }
+
+    fn next(&self) -> u32 {
+        match self.cur {
+            NIL => self.deque.head,
+            cur => self.deque.node(cur).next,
+        }
+    }
+
+    fn prev(&self) -> u32 {
+        match self.cur {
+            NIL => self.deque.tail,
+            cur => self.deque.node(cur).prev,
+        }
+    }
+}
+
+impl<T> IntoIterator for ArenaDeque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArenaDeque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ArenaDeque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T>(ArenaDeque<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.size, Some(self.0.size))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> std::iter::FusedIterator for IntoIter<T> {}
+
+pub struct Iter<'a, T> {
+    slab: &'a [Slot<T>],
+    head: u32,
+    tail: u32,
+    size: usize,
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            slab: self.slab,
+            head: self.head,
+            tail: self.tail,
+            size: self.size,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            None
+        } else {
+            match &self.slab[self.head as usize] {
+                Slot::Occupied(node) => {
+                    self.head = node.next;
+                    self.size -= 1;
+                    Some(&node.elem)
+                }
+                Slot::Vacant(_) => unreachable!("Link to a free slot"),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.size, Some(self.size))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            None
+        } else {
+            match &self.slab[self.tail as usize] {
+                Slot::Occupied(node) => {
+                    self.tail = node.prev;
+                    self.size -= 1;
+                    Some(&node.elem)
+                }
+                Slot::Vacant(_) => unreachable!("Link to a free slot"),
+            }
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> std::iter::FusedIterator for Iter<'_, T> {}
+
+/// Nodes are reached through a raw pointer to the slab since safe code has no way of knowing that
+/// the iterator never yields the same node twice.
+pub struct IterMut<'a, T> {
+    slab: *mut Slot<T>,
+    head: u32,
+    tail: u32,
+    size: usize,
+    _boo: std::marker::PhantomData<&'a mut T>,
+}
+
+// SAFETY: `IterMut` behaves like a `&mut [T]`
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            None
+        } else {
+            match unsafe { &mut *self.slab.add(self.head as usize) } {
+                Slot::Occupied(node) => {
+                    self.head = node.next;
+                    self.size -= 1;
+                    Some(&mut node.elem)
+                }
+                Slot::Vacant(_) => unreachable!("Link to a free slot"),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.size, Some(self.size))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            None
+        } else {
+            match unsafe { &mut *self.slab.add(self.tail as usize) } {
+                Slot::Occupied(node) => {
+                    self.tail = node.prev;
+                    self.size -= 1;
+                    Some(&mut node.elem)
+                }
+                Slot::Vacant(_) => unreachable!("Link to a free slot"),
+            }
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> std::iter::FusedIterator for IterMut<'_, T> {}
+
+#[cfg(test)]
+mod test {
+    use dsa_util::DropCounter;
+
+    use super::*;
+
+    fn to_vec<T: Clone>(deque: &ArenaDeque<T>) -> Vec<T> {
+        deque.iter().cloned().collect()
+    }
+
+    #[test]
+    fn arena_deque_new() {
+        let deque = ArenaDeque::<()>::new();
+        assert_eq!(deque.head, NIL);
+        assert_eq!(deque.tail, NIL);
+        assert_eq!(deque.free, NIL);
+        assert_eq!(deque.len(), 0);
+        assert!(deque.is_empty());
+
+        let deque = ArenaDeque::<()>::with_capacity(10);
+        assert!(deque.capacity() >= 10);
+    }
+
+    #[test]
+    fn arena_deque_push_pop() {
+        let mut deque = ArenaDeque::new();
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+
+        for n in 0..10 {
+            deque.push_back(n);
+        }
+        for n in 0..5 {
+            assert_eq!(deque.pop_front(), Some(n));
+        }
+        for n in (5..10).rev() {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.head, NIL);
+        assert_eq!(deque.tail, NIL);
+        assert!(deque.is_empty());
+
+        for n in 0..10 {
+            deque.push_front(n);
+        }
+        for n in 0..10 {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn arena_deque_reuse_slots() {
+        let mut deque = ArenaDeque::new();
+        for n in 0..10 {
+            deque.push_back(n);
+        }
+
+        for _ in 0..100 {
+            let n = deque.pop_front().unwrap();
+            deque.push_back(n);
+        }
+
+        // Freed slots are reused, so the slab never grows past the largest size of the deque
+        assert_eq!(deque.slab.len(), 10);
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn arena_deque_peek() {
+        let mut deque = ArenaDeque::new();
+        assert_eq!(deque.peek_front(), None);
+        assert_eq!(deque.peek_back(), None);
+        assert_eq!(deque.peek_front_mut(), None);
+        assert_eq!(deque.peek_back_mut(), None);
+
+        for n in 0..10 {
+            deque.push_back(n);
+            assert_eq!(deque.peek_front(), Some(&0));
+            assert_eq!(deque.peek_back(), Some(&n));
+        }
+
+        *deque.peek_front_mut().unwrap() += 10;
+        *deque.peek_back_mut().unwrap() += 10;
+        assert_eq!(deque.peek_front(), Some(&10));
+        assert_eq!(deque.peek_back(), Some(&19));
+    }
+
+    #[test]
+    fn arena_deque_clear() {
+        let mut deque = (0..10).collect::<ArenaDeque<_>>();
+        deque.clear();
+
+        assert!(deque.is_empty());
+        assert_eq!(deque.pop_front(), None);
+
+        deque.push_back(0);
+        assert_eq!(to_vec(&deque), [0]);
+    }
+
+    #[test]
+    fn arena_deque_append() {
+        let mut deque = (0..5).collect::<ArenaDeque<_>>();
+        let mut other = (5..10).collect::<ArenaDeque<_>>();
+
+        deque.append(&mut other);
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(other.is_empty());
+
+        let mut empty = ArenaDeque::new();
+        deque.append(&mut empty);
+        assert_eq!(deque.len(), 10);
+        empty.append(&mut deque);
+        assert_eq!(empty.len(), 10);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn arena_deque_cursor_walk() {
+        let deque = (0..5).collect::<ArenaDeque<_>>();
+
+        let mut cursor = deque.cursor_front();
+        for n in 0..5 {
+            assert_eq!(cursor.index(), Some(n));
+            assert_eq!(cursor.current(), Some(&(n as i32)));
+            cursor.move_next();
+        }
+
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&0));
+        assert_eq!(cursor.peek_prev(), Some(&4));
+
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&0));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(4));
+
+        let mut cursor = deque.cursor_back();
+        for n in (0..5).rev() {
+            assert_eq!(cursor.index(), Some(n));
+            assert_eq!(cursor.current(), Some(&(n as i32)));
+            cursor.move_prev();
+        }
+        assert_eq!(cursor.index(), None);
+
+        let empty = ArenaDeque::<i32>::new();
+        let mut cursor = empty.cursor_back();
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+    }
+
+    #[test]
+    fn arena_deque_cursor_mut_edit() {
+        let mut deque = ArenaDeque::new();
+        deque.push_back(1);
+        deque.push_back(3);
+
+        let mut cursor = deque.cursor_front_mut();
+        cursor.insert_before(0);
+        cursor.insert_after(2);
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 1));
+
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+        cursor.insert_before(5);
+        cursor.insert_after(-1);
+        assert_eq!(cursor.peek_next(), Some(&mut -1));
+        assert_eq!(cursor.peek_prev(), Some(&mut 5));
+        assert_eq!(cursor.as_cursor().peek_prev(), Some(&5));
+
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(5));
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.remove_current(), None);
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(-1));
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.current(), Some(&mut 0));
+
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3]);
+        for n in (0..4).rev() {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn arena_deque_cursor_mut_splice() {
+        let mut deque = ArenaDeque::new();
+        deque.push_back(0);
+        deque.push_back(5);
+
+        let mut cursor = deque.cursor_back_mut();
+        cursor.splice_before((1..3).collect());
+        assert_eq!(cursor.index(), Some(3));
+        assert_eq!(cursor.current(), Some(&mut 5));
+
+        cursor.move_prev();
+        cursor.splice_after((3..5).collect());
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.current(), Some(&mut 2));
+
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        cursor.splice_before((6..8).collect());
+        cursor.splice_after((-2..0).collect());
+        cursor.splice_after(ArenaDeque::new());
+        cursor.splice_before(ArenaDeque::new());
+
+        assert_eq!(deque.len(), 10);
+        assert_eq!(to_vec(&deque), (-2..8).collect::<Vec<_>>());
+        assert_eq!(
+            deque.iter().rev().copied().collect::<Vec<_>>(),
+            (-2..8).rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn arena_deque_iter() {
+        let mut deque = (0..10).collect::<ArenaDeque<_>>();
+
+        let mut iter = deque.iter();
+        assert_eq!(iter.len(), 10);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&9));
+        assert_eq!(iter.len(), 8);
+        assert_eq!(iter.clone().count(), 8);
+
+        for elem in deque.iter_mut().rev() {
+            *elem += 1;
+        }
+        for elem in &mut deque {
+            *elem *= 2;
+        }
+        assert_eq!(to_vec(&deque), [2, 4, 6, 8, 10, 12, 14, 16, 18, 20]);
+
+        let mut iter = deque.into_iter();
+        assert_eq!(iter.next_back(), Some(20));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.len(), 8);
+    }
+
+    #[test]
+    fn arena_deque_iter_after_reuse() {
+        let mut deque = ArenaDeque::new();
+        for n in 0..5 {
+            deque.push_back(n);
+        }
+
+        // Slots are now out of order in the slab
+        deque.pop_front();
+        deque.pop_front();
+        deque.push_front(1);
+        deque.push_back(5);
+
+        assert_eq!(to_vec(&deque), [1, 2, 3, 4, 5]);
+        assert_eq!(
+            deque.iter().rev().copied().collect::<Vec<_>>(),
+            [5, 4, 3, 2, 1]
+        );
+        assert_eq!(
+            deque.iter_mut().map(|n| *n).collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn arena_deque_traits() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let mut deque = (0..5).collect::<ArenaDeque<_>>();
+        deque.extend(5..8);
+        deque.extend(&[8, 9]);
+        assert_eq!(&format!("{deque:?}"), "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
+
+        let mut deque_clone = deque.clone();
+        assert_eq!(deque, deque_clone);
+        assert_eq!(deque_clone.pop_front(), Some(0));
+        assert_ne!(deque, deque_clone);
+        assert_eq!(deque.len(), 10);
+
+        let state = RandomState::new();
+        assert_eq!(
+            state.hash_one(&deque),
+            state.hash_one((0..10).collect::<ArenaDeque<_>>())
+        );
+        assert_eq!(ArenaDeque::<i32>::new(), ArenaDeque::default());
+    }
+
+    #[test]
+    fn arena_deque_drop() {
+        let mut deque = ArenaDeque::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..10 {
+            deque.push_back(DropCounter::new(&rc, vec![n]));
+        }
+
+        drop(deque.pop_front());
+        let mut cursor = deque.cursor_front_mut();
+        drop(cursor.remove_current());
+        assert_eq!(rc.get(), 2);
+
+        // Popped slots should not be dropped a second time
+        drop(deque);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn arena_deque_drop_into_iter() {
+        let mut deque = ArenaDeque::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..10 {
+            deque.push_back(DropCounter::new(&rc, vec![n]));
+        }
+
+        let mut iter = deque.into_iter();
+        drop(iter.next());
+        assert_eq!(rc.get(), 1);
+
+        drop(iter);
+        assert_eq!(rc.get(), 10);
+    }
+}
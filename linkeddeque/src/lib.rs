@@ -1,196 +1,2732 @@
-pub struct LinkedDeque<T> {
+use allocator_api2::{
+    alloc::{Allocator, Global},
+    boxed::Box,
+};
+use itertools::{
+    FoldWhile::{Continue, Done},
+    Itertools,
+};
+
+pub mod arena;
+
+pub use arena::ArenaDeque;
+
+/// A doubly-linked deque.
+///
+/// Nodes are allocated with `A`, which defaults to the global allocator. Use
+/// [`new_in`](Self::new_in) to allocate them somewhere else, such as a bump arena.
+pub struct LinkedDeque<T, A: Allocator = Global> {
     head: Link<T>,
-    tail: *mut Node<T>,
+    tail: Link<T>,
     size: usize,
+    handles: Vec<HandleSlot<T>>,
+    free_handles: Vec<u32>,
+    /// Id of the current handle table, see [`next_table`].
+    table: u64,
+    alloc: A,
+    _boo: std::marker::PhantomData<T>,
 }
 
-type Link<T> = Option<Box<Node<T>>>;
+type Link<T> = Option<std::ptr::NonNull<Node<T>>>;
+
+/// The first node, last node and length of a detached sequence of nodes.
+type Chain<T> = (
+    std::ptr::NonNull<Node<T>>,
+    std::ptr::NonNull<Node<T>>,
+    usize,
+);
 
 struct Node<T> {
     elem: T,
     next: Link<T>,
-    // prev: Link<T>,
+    prev: Link<T>,
+    /// Index of this node's slot in [`LinkedDeque::handles`], or [`NO_HANDLE`].
+    handle: u32,
+    /// Id of the handle table `handle` indexes into. The slot only belongs to this node if this
+    /// is still the table of the deque holding it.
+    table: u64,
+}
+
+/// Marks a node which has no [`NodeHandle`] pointing to it.
+const NO_HANDLE: u32 = u32::MAX;
+
+/// Returns a new handle table id. Ids are never reused, so that handles and nodes coming from
+/// another deque, or from a table which has since been reset, are never mistaken for current ones.
+fn next_table() -> u64 {
+    static NEXT_TABLE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+    NEXT_TABLE.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+/// A stable reference to an element of a [`LinkedDeque`], which stays valid as other elements are
+/// added or removed around it.
+///
+/// Handles never point to a node directly. Instead they index into a table of slots kept by the
+/// deque, alongside a generation counter which is bumped whenever the slot's node leaves the
+/// deque. Using a handle after its element has been removed, or moved to another deque by
+/// [`append`] or [`split_off`], is therefore detected and returns [`None`].
+///
+/// Each table also has a unique id, which is stamped on its handles. Passing a handle to another
+/// deque returns [`None`] as well.
+///
+/// [`append`]: LinkedDeque::append
+/// [`split_off`]: LinkedDeque::split_off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeHandle {
+    table: u64,
+    index: u32,
+    generation: u32,
+}
+
+/// `node` is [`None`] while the slot is on the free list.
+struct HandleSlot<T> {
+    generation: u32,
+    node: Link<T>,
+}
+
+// SAFETY: `LinkedDeque` owns all of its nodes uniquely, so it is as thread-safe as a `Box<T>`.
+// Iterators and cursors follow the same rules as `&T` and `&mut T`.
+unsafe impl<T: Send, A: Allocator + Send> Send for LinkedDeque<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for LinkedDeque<T, A> {}
+
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+unsafe impl<T: Sync, A: Allocator + Sync> Send for Cursor<'_, T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for Cursor<'_, T, A> {}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for CursorMut<'_, T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for CursorMut<'_, T, A> {}
+
+impl<T: std::fmt::Debug, A: Allocator> std::fmt::Debug for LinkedDeque<T, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for LinkedDeque<T, A> {
+    fn clone(&self) -> Self {
+        let mut deque = Self::new_in(self.alloc.clone());
+        deque.extend(self.iter().cloned());
+        deque
+    }
+}
+
+impl<T: PartialEq, A: Allocator> PartialEq for LinkedDeque<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size
+            && self
+                .iter()
+                .zip(other.iter())
+                .fold_while(
+                    true,
+                    |_, (a, b)| {
+                        if a == b { Continue(true) } else { Done(false) }
+                    },
+                )
+                .into_inner()
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for LinkedDeque<T, A> {}
+
+impl<T: std::hash::Hash, A: Allocator> std::hash::Hash for LinkedDeque<T, A> {
+    /// The length is hashed first so that nested deques with the same flattened contents do not
+    /// collide, as is done for [`Vec`].
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.size.hash(state);
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for LinkedDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Self::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for LinkedDeque<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T> for LinkedDeque<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
 }
 
-// impl<T> Drop for LinkedDeque<T> {
-//     fn drop(&mut self) {
-//         while self.pop_front().is_some() {}
-//     }
-// }
+impl<T, A: Allocator> Drop for LinkedDeque<T, A> {
+    /// Nodes are popped one at a time so that dropping a long deque does not recurse.
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> Default for LinkedDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl<T> LinkedDeque<T> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: Allocator> LinkedDeque<T, A> {
+    /// Whether nodes allocated by another deque can be relinked into this one, which then frees
+    /// them with its own allocator.
+    ///
+    /// Allocators are not required to free memory allocated by another instance, and instances
+    /// cannot be compared in general, so this only holds for zero-sized allocators such as
+    /// [`Global`]: they have no state which could tell two instances apart.
+    const SHARES_NODES: bool = std::mem::size_of::<A>() == 0;
+
+    /// Creates an empty deque whose nodes are allocated with `alloc`.
+    pub fn new_in(alloc: A) -> Self {
         Self {
             head: None,
-            tail: std::ptr::null_mut(),
+            tail: None,
             size: 0,
+            handles: Vec::new(),
+            free_handles: Vec::new(),
+            table: next_table(),
+            alloc,
+            _boo: std::marker::PhantomData,
         }
     }
 
-    pub fn push(&mut self, elem: T) {
-        let node = Box::new(Node::new(elem));
+    /// Returns a reference to the allocator used by the deque.
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let node = Node::alloc(elem, &self.alloc);
 
-        let new_tail = match self.tail.take() {
-            Some(tail) => {
-                tail.next = Some(node);
-                tail.next.as_deref_mut()
+        unsafe {
+            match self.head {
+                Some(head) => {
+                    (*head.as_ptr()).prev = Some(node);
+                    (*node.as_ptr()).next = Some(head);
+                }
+                None => self.tail = Some(node),
             }
-            None => {
-                self.head = Some(node);
-                self.head.as_deref_mut()
+        }
+
+        self.head = Some(node);
+        self.size += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let node = Node::alloc(elem, &self.alloc);
+
+        unsafe {
+            match self.tail {
+                Some(tail) => {
+                    (*tail.as_ptr()).next = Some(node);
+                    (*node.as_ptr()).prev = Some(tail);
+                }
+                None => self.head = Some(node),
+            }
+        }
+
+        self.tail = Some(node);
+        self.size += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|head| unsafe { self.unlink_node(head).elem })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|tail| unsafe { self.unlink_node(tail).elem })
+    }
+
+    /// Like [`push_front`], but returns a [`NodeHandle`] to the new element.
+    ///
+    /// [`push_front`]: Self::push_front
+    pub fn push_front_handle(&mut self, elem: T) -> NodeHandle {
+        self.push_front(elem);
+        // The unwrap is safe since we just pushed an element
+        unsafe { self.make_handle(self.head.unwrap_unchecked()) }
+    }
+
+    /// Like [`push_back`], but returns a [`NodeHandle`] to the new element.
+    ///
+    /// [`push_back`]: Self::push_back
+    pub fn push_back_handle(&mut self, elem: T) -> NodeHandle {
+        self.push_back(elem);
+        // The unwrap is safe since we just pushed an element
+        unsafe { self.make_handle(self.tail.unwrap_unchecked()) }
+    }
+
+    /// Returns the element pointed to by `handle`, or [`None`] if it is no longer in the deque.
+    pub fn get(&self, handle: NodeHandle) -> Option<&T> {
+        self.resolve(handle)
+            .map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    /// Returns the element pointed to by `handle`, or [`None`] if it is no longer in the deque.
+    pub fn get_mut(&mut self, handle: NodeHandle) -> Option<&mut T> {
+        self.resolve(handle)
+            .map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    /// Removes the element pointed to by `handle` in O(1), or returns [`None`] if it is no longer
+    /// in the deque. This invalidates `handle`.
+    pub fn remove(&mut self, handle: NodeHandle) -> Option<T> {
+        self.resolve(handle)
+            .map(|node| unsafe { self.unlink_node(node).elem })
+    }
+
+    /// Moves the element pointed to by `handle` to the front of the deque in O(1), or returns
+    /// [`None`] if it is no longer in the deque. `handle` stays valid.
+    pub fn move_to_front(&mut self, handle: NodeHandle) -> Option<&mut T> {
+        self.resolve(handle).map(|node| unsafe {
+            self.detach_node(node);
+            self.link_chain(None, self.head, node, node, 1);
+            &mut (*node.as_ptr()).elem
+        })
+    }
+
+    /// Moves the element pointed to by `handle` to the back of the deque in O(1), or returns
+    /// [`None`] if it is no longer in the deque. `handle` stays valid.
+    pub fn move_to_back(&mut self, handle: NodeHandle) -> Option<&mut T> {
+        self.resolve(handle).map(|node| unsafe {
+            self.detach_node(node);
+            self.link_chain(self.tail, None, node, node, 1);
+            &mut (*node.as_ptr()).elem
+        })
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        self.head.map(|head| unsafe { &(*head.as_ptr()).elem })
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        self.tail.map(|tail| unsafe { &(*tail.as_ptr()).elem })
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        self.head.map(|head| unsafe { &mut (*head.as_ptr()).elem })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        self.tail.map(|tail| unsafe { &mut (*tail.as_ptr()).elem })
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Moves all the elements of `other` to the back of the deque, leaving `other` empty.
+    ///
+    /// With a zero-sized allocator such as [`Global`], this is O(1) as it only relinks the ends
+    /// of both deques. Otherwise the nodes of `other` may not be freed by the allocator of this
+    /// deque, so the elements are moved over one at a time in O(n).
+    pub fn append(&mut self, other: &mut Self) {
+        if !Self::SHARES_NODES {
+            while let Some(elem) = other.pop_front() {
+                self.push_back(elem);
+            }
+        } else if let Some((first, last, len)) = other.take_chain() {
+            unsafe { self.link_chain(self.tail, None, first, last, len) };
+        }
+    }
+
+    /// Moves all the elements of `other` to the front of the deque, leaving `other` empty.
+    ///
+    /// Like [`append`](Self::append), this is O(1) with a zero-sized allocator and O(n)
+    /// otherwise.
+    pub fn prepend(&mut self, other: &mut Self) {
+        if !Self::SHARES_NODES {
+            while let Some(elem) = other.pop_back() {
+                self.push_front(elem);
+            }
+        } else if let Some((first, last, len)) = other.take_chain() {
+            unsafe { self.link_chain(None, self.head, first, last, len) };
+        }
+    }
+
+    /// Splits the deque in two at index `at`, returning everything from `at` onwards.
+    ///
+    /// Finding the split point walks from whichever end of the deque is closest to `at`, after
+    /// which the split itself is O(1). If handles to the elements of the deque are still live,
+    /// the elements which move out are also walked to invalidate their handles, stopping early
+    /// once no live handle is left.
+    ///
+    /// # Panics
+    ///
+    /// If `at > self.len()`.
+    pub fn split_off(&mut self, at: usize) -> Self
+    where
+        A: Clone,
+    {
+        assert!(
+            at <= self.size,
+            "Cannot split off at index {at}, deque only has {} elements",
+            self.size
+        );
+
+        if at == self.size {
+            return Self::new_in(self.alloc.clone());
+        } else if at == 0 {
+            // This cannot just swap out `self` as handles to its elements would then be left
+            // pointing into the new deque. The nodes can be relinked whatever the allocator, since
+            // the new deque frees them with a clone of it
+            let mut other = Self::new_in(self.alloc.clone());
+            if let Some((first, last, len)) = self.take_chain() {
+                unsafe { other.link_chain(None, None, first, last, len) };
+            }
+            return other;
+        }
+
+        unsafe {
+            // The last node to stay in `self`. Both unwraps are safe since `0 < at < self.size`
+            let last = if at <= self.size / 2 {
+                let mut node = self.head.unwrap_unchecked();
+                for _ in 1..at {
+                    node = (*node.as_ptr()).next.unwrap_unchecked();
+                }
+                node
+            } else {
+                let mut node = self.tail.unwrap_unchecked();
+                for _ in at..self.size {
+                    node = (*node.as_ptr()).prev.unwrap_unchecked();
+                }
+                node
+            };
+
+            let first = (*last.as_ptr()).next.take().unwrap_unchecked();
+            (*first.as_ptr()).prev = None;
+
+            let mut node = Some(first);
+            while let Some(inner) = node
+                && self.handles.len() > self.free_handles.len()
+            {
+                self.release_handle(&mut *inner.as_ptr());
+                node = (*inner.as_ptr()).next;
+            }
+
+            let mut other = Self::new_in(self.alloc.clone());
+            other.head = Some(first);
+            other.tail = self.tail.replace(last);
+            other.size = self.size - at;
+            self.size = at;
+
+            other
+        }
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, see [`retain_mut`](Self::retain_mut).
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_mut(|elem| f(elem));
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, visiting them from front to back.
+    /// Rejected nodes are unlinked in place, which invalidates any handle to them.
+    ///
+    /// If `f` panics, the elements visited so far have been filtered and the rest are kept.
+    pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        let mut cursor = self.cursor_front_mut();
+
+        while let Some(elem) = cursor.current() {
+            if f(elem) {
+                cursor.move_next();
+            } else {
+                cursor.remove_current();
+            }
+        }
+    }
+
+    /// Returns an iterator which lazily removes and yields the elements for which `filter`
+    /// returns `true`, from front to back.
+    ///
+    /// Elements which are not yielded, either because `filter` returned `false` or because the
+    /// iterator was dropped early, stay in the deque. If `filter` panics, every element the
+    /// iterator has not yielded stays in the deque.
+    pub fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, filter: F) -> ExtractIf<'_, T, F, A> {
+        ExtractIf {
+            cursor: self.cursor_front_mut(),
+            filter,
+        }
+    }
+
+    /// Removes and returns the frontmost element for which `pred` returns `true`.
+    pub fn remove_first<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> Option<T> {
+        self.extract_if(|elem| pred(elem)).next()
+    }
+
+    /// Removes consecutive repeated elements, see [`dedup_by`](Self::dedup_by).
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+
+    /// Removes consecutive elements which map to the same key, see [`dedup_by`](Self::dedup_by).
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes consecutive elements for which `same_bucket` returns `true`, keeping the first one
+    /// from the front.
+    ///
+    /// `same_bucket(a, b)` is passed each element `a` along with the last element `b` kept before
+    /// it, just like [`Vec::dedup_by`]. If it panics, the elements visited so far have been
+    /// deduplicated and the rest are kept.
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same_bucket: F) {
+        let Some(mut kept) = self.head else {
+            return;
+        };
+
+        while let Some(node) = unsafe { (*kept.as_ptr()).next } {
+            let same =
+                unsafe { same_bucket(&mut (*node.as_ptr()).elem, &mut (*kept.as_ptr()).elem) };
+
+            if same {
+                drop(unsafe { self.unlink_node(node) });
+            } else {
+                kept = node;
             }
+        }
+    }
+
+    /// Sorts the deque from front to back, see [`sort_by`](Self::sort_by).
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    /// Sorts the deque from front to back by the key extracted with `f`, see
+    /// [`sort_by`](Self::sort_by).
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    /// Sorts the deque from front to back with a stable, bottom-up merge sort.
+    ///
+    /// This runs in O(n log n) time and O(1) extra space: elements are never moved, only the
+    /// nodes holding them are relinked, so handles to them stay valid. Runs are merged along the
+    /// `next` links only, and `prev` links are rebuilt in a single pass at the end. If `compare`
+    /// panics, the deque is left holding all of its elements in an unspecified order.
+    pub fn sort_by<F: FnMut(&T, &T) -> std::cmp::Ordering>(&mut self, mut compare: F) {
+        if self.size < 2 {
+            return;
+        }
+
+        let size = self.size;
+        let mut sort = MergeSort {
+            unsorted: self.head.take(),
+            sorted: None,
+            sorted_tail: None,
+            left: None,
+            right: None,
+            deque: self,
         };
+        sort.deque.tail = None;
+
+        // Each pass merges pairs of sorted runs of length `width` into runs of length `2 * width`
+        let mut width = 1;
+        while width < size {
+            while sort.unsorted.is_some() {
+                sort.left = sort.unsorted.take();
+                sort.right = unsafe { split_after(sort.left, width) };
+                sort.unsorted = unsafe { split_after(sort.right, width) };
+
+                loop {
+                    // Ties go to the left run, which is what keeps the sort stable
+                    let take_right = match (sort.left, sort.right) {
+                        (Some(left), Some(right)) => unsafe {
+                            compare(&(*right.as_ptr()).elem, &(*left.as_ptr()).elem)
+                                == std::cmp::Ordering::Less
+                        },
+                        (Some(_), None) => false,
+                        (None, Some(_)) => true,
+                        (None, None) => break,
+                    };
+
+                    let run = if take_right {
+                        &mut sort.right
+                    } else {
+                        &mut sort.left
+                    };
+
+                    unsafe {
+                        // The unwrap is safe since we checked the run was not empty above
+                        let node = run.unwrap_unchecked();
+                        *run = (*node.as_ptr()).next.take();
+
+                        match sort.sorted_tail {
+                            Some(tail) => (*tail.as_ptr()).next = Some(node),
+                            None => sort.sorted = Some(node),
+                        }
+                        sort.sorted_tail = Some(node);
+                    }
+                }
+            }
+
+            sort.unsorted = sort.sorted.take();
+            sort.sorted_tail = None;
+            width *= 2;
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            size: self.size,
+            _boo: std::marker::PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            size: self.size,
+            _boo: std::marker::PhantomData,
+        }
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            cur: self.head,
+            index: self.head.map(|_| 0),
+            deque: self,
+        }
+    }
 
-        self.tail = new_tail;
+    pub fn cursor_back(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            cur: self.tail,
+            index: self.tail.map(|_| self.size - 1),
+            deque: self,
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            cur: self.head,
+            index: self.head.map(|_| 0),
+            deque: self,
+        }
     }
 
-    pub fn pop(&mut self) -> Option<T> {
-        self.head.take().map(|head| {
-            match head.next {
-                Some(next) => self.head = Some(next),
-                None => self.tail = None,
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            cur: self.tail,
+            index: self.tail.map(|_| self.size - 1),
+            deque: self,
+        }
+    }
+
+    /// Links the chain of nodes going from `first` to `last` in between `prev` and `next`, which
+    /// must be adjacent in the deque. A `prev` or `next` of [`None`] refers to the front or back
+    /// of the deque respectively.
+    ///
+    /// # Safety
+    ///
+    /// `first` to `last` must form a valid chain of `len` nodes which are not part of any deque.
+    unsafe fn link_chain(
+        &mut self,
+        prev: Link<T>,
+        next: Link<T>,
+        first: std::ptr::NonNull<Node<T>>,
+        last: std::ptr::NonNull<Node<T>>,
+        len: usize,
+    ) {
+        unsafe {
+            (*first.as_ptr()).prev = prev;
+            (*last.as_ptr()).next = next;
+
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(first),
+                None => self.head = Some(first),
             }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(last),
+                None => self.tail = Some(last),
+            }
+        }
 
-            head.elem
-        })
+        self.size += len;
+    }
+
+    /// Removes `node` from the deque and hands back ownership of its allocation, invalidating
+    /// any handle to it.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be part of this deque.
+    unsafe fn unlink_node(&mut self, node: std::ptr::NonNull<Node<T>>) -> Node<T> {
+        unsafe {
+            self.detach_node(node);
+            self.release_handle(&mut *node.as_ptr());
+            Box::into_inner(Box::from_raw_in(node.as_ptr(), &self.alloc))
+        }
     }
 
-    // pub fn push_front(&mut self, elem: T) {
-    //     let node = std::rc::Rc::new(std::cell::RefCell::new(Node::new(elem)));
-    //
-    //     match self.head.take() {
-    //         Some(head) => {
-    //             node.borrow_mut().next = Some(std::rc::Rc::clone(&head));
-    //             head.borrow_mut().prev = Some(std::rc::Rc::clone(&node));
-    //             self.head = Some(node);
-    //         }
-    //         None => {
-    //             self.head = Some(std::rc::Rc::clone(&node));
-    //             self.tail = Some(node);
-    //         }
-    //     };
-    //
-    //     self.size += 1;
-    // }
-    //
-    // pub fn push_back(&mut self, elem: T) {
-    //     let node = std::rc::Rc::new(std::cell::RefCell::new(Node::new(elem)));
-    //
-    //     match self.tail.take() {
-    //         Some(tail) => {
-    //             node.borrow_mut().prev = Some(std::rc::Rc::clone(&tail));
-    //             tail.borrow_mut().next = Some(std::rc::Rc::clone(&node));
-    //             self.tail = Some(node);
-    //         }
-    //         None => {
-    //             self.head = Some(std::rc::Rc::clone(&node));
-    //             self.tail = Some(node);
-    //         }
-    //     };
-    //
-    //     self.size += 1;
-    // }
-    //
-    // pub fn pop_front(&mut self) -> Option<T> {
-    //     self.head.take().map(|head| {
-    //         match head.borrow_mut().next.take() {
-    //             Some(next) => {
-    //                 next.borrow_mut().prev = None;
-    //                 self.head = Some(next);
-    //             }
-    //             None => {
-    //                 self.tail = None;
-    //             }
-    //         };
-    //
-    //         self.size -= 1;
-    //
-    //         std::rc::Rc::try_unwrap(head)
-    //             .ok()
-    //             .unwrap()
-    //             .into_inner()
-    //             .elem
-    //     })
-    // }
-    //
-    // pub fn pop_back(&mut self) -> Option<T> {
-    //     self.tail.take().map(|tail| {
-    //         match tail.borrow_mut().prev.take() {
-    //             Some(prev) => {
-    //                 prev.borrow_mut().next = None;
-    //                 self.tail = Some(prev);
-    //             }
-    //             None => {
-    //                 self.head = None;
-    //             }
-    //         };
-    //
-    //         self.size -= 1;
-    //
-    //         std::rc::Rc::try_unwrap(tail)
-    //             .ok()
-    //             .unwrap()
-    //             .into_inner()
-    //             .elem
-    //     })
-    // }
-    //
-    // pub fn peek_front(&self) -> Option<std::cell::Ref<T>> {
-    //     self.head
-    //         .as_ref()
-    //         .map(|head| std::cell::Ref::map(head.borrow(), |head| &head.elem))
-    // }
-    //
-    // pub fn peek_back(&self) -> Option<std::cell::Ref<T>> {
-    //     self.tail
-    //         .as_ref()
-    //         .map(|tail| std::cell::Ref::map(tail.borrow(), |head| &head.elem))
-    // }
-    //
-    // pub fn peek_front_mut(&mut self) -> Option<std::cell::RefMut<T>> {
-    //     self.head
-    //         .as_ref()
-    //         .map(|head| std::cell::RefMut::map(head.borrow_mut(), |head| &mut head.elem))
-    // }
-    //
-    // pub fn peek_back_mut(&mut self) -> Option<std::cell::RefMut<T>> {
-    //     self.tail
-    //         .as_ref()
-    //         .map(|tail| std::cell::RefMut::map(tail.borrow_mut(), |head| &mut head.elem))
-    // }
-}
-
-// impl<T> IntoIterator for LinkedDeque<T> {
-//     type Item = T;
-//     type IntoIter = IntoIter<T>;
-//
-//     fn into_iter(self) -> Self::IntoIter {
-//         IntoIter(self)
-//     }
-// }
-//
-// pub struct IntoIter<T>(LinkedDeque<T>);
-//
-// impl<T> Iterator for IntoIter<T> {
-//     type Item = T;
-//
-//     fn next(&mut self) -> Option<Self::Item> {
-//         self.0.pop_front()
-//     }
-// }
-//
-// impl<T> DoubleEndedIterator for IntoIter<T> {
-//     fn next_back(&mut self) -> Option<Self::Item> {
-//         self.0.pop_back()
-//     }
-// }
+    /// Unlinks `node` from its neighbours without freeing it, so that it can be linked back in
+    /// somewhere else. The links inside of `node` itself are left dangling.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be part of this deque.
+    unsafe fn detach_node(&mut self, node: std::ptr::NonNull<Node<T>>) {
+        unsafe {
+            let Node { prev, next, .. } = *node.as_ptr();
 
-impl<T> Node<T> {
-    fn new(elem: T) -> Self {
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = next,
+                None => self.head = next,
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = prev,
+                None => self.tail = prev,
+            }
+        }
+
+        self.size -= 1;
+    }
+
+    /// Takes ownership of all the nodes in the deque, leaving it empty.
+    ///
+    /// Every handle to those nodes is invalidated in O(1) by starting a new handle table. The
+    /// nodes keep the id of the old one, so they are known not to have a handle wherever they end
+    /// up.
+    fn take_chain(&mut self) -> Option<Chain<T>> {
+        if !self.handles.is_empty() {
+            self.handles.clear();
+            self.free_handles.clear();
+            self.table = next_table();
+        }
+
+        let size = std::mem::take(&mut self.size);
+        self.head
+            .take()
+            .zip(self.tail.take())
+            .map(|(head, tail)| (head, tail, size))
+    }
+
+    /// Assigns a handle slot to `node`, reusing a free slot if there is one.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be part of this deque and not already have a handle.
+    unsafe fn make_handle(&mut self, node: std::ptr::NonNull<Node<T>>) -> NodeHandle {
+        let index = match self.free_handles.pop() {
+            Some(index) => {
+                self.handles[index as usize].node = Some(node);
+                index
+            }
+            None => {
+                assert!(
+                    self.handles.len() < NO_HANDLE as usize,
+                    "Cannot create more handles, handle table is full"
+                );
+                self.handles.push(HandleSlot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                (self.handles.len() - 1) as u32
+            }
+        };
+
+        unsafe {
+            (*node.as_ptr()).handle = index;
+            (*node.as_ptr()).table = self.table;
+        }
+
+        NodeHandle {
+            table: self.table,
+            index,
+            generation: self.handles[index as usize].generation,
+        }
+    }
+
+    /// Frees the handle slot of `node`, if it has one in the current table, so that existing
+    /// handles to it no longer resolve.
+    fn release_handle(&mut self, node: &mut Node<T>) {
+        if node.handle != NO_HANDLE && node.table == self.table {
+            let slot = &mut self.handles[node.handle as usize];
+            slot.generation = slot.generation.wrapping_add(1);
+            slot.node = None;
+            self.free_handles.push(node.handle);
+            node.handle = NO_HANDLE;
+        }
+    }
+
+    fn resolve(&self, handle: NodeHandle) -> Link<T> {
+        if handle.table != self.table {
+            return None;
+        }
+
+        self.handles
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.node)
+    }
+}
+
+/// A read-only cursor over a [`LinkedDeque`].
+///
+/// A cursor points either to an element of the deque or to a "ghost" non-element which sits
+/// between the back and the front of the deque. Moving past either end of the deque will land the
+/// cursor on the ghost, and moving again will wrap it around to the other end.
+pub struct Cursor<'a, T, A: Allocator = Global> {
+    cur: Link<T>,
+    index: Option<usize>,
+    deque: &'a LinkedDeque<T, A>,
+}
+
+impl<T, A: Allocator> Clone for Cursor<'_, T, A> {
+    fn clone(&self) -> Self {
         Self {
-            elem,
-            next: None,
-            prev: None,
+            cur: self.cur,
+            index: self.index,
+            deque: self.deque,
+        }
+    }
+}
+
+impl<'a, T, A: Allocator> Cursor<'a, T, A> {
+    /// The index of the element under the cursor, or [`None`] if it is on the ghost element.
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        match self.cur {
+            Some(cur) => unsafe {
+                self.cur = (*cur.as_ptr()).next;
+                self.index = self.cur.and_then(|_| self.index.map(|index| index + 1));
+            },
+            None => {
+                self.cur = self.deque.head;
+                self.index = self.cur.map(|_| 0);
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match self.cur {
+            Some(cur) => unsafe {
+                self.cur = (*cur.as_ptr()).prev;
+                self.index = self.cur.and_then(|_| self.index.map(|index| index - 1));
+            },
+            None => {
+                self.cur = self.deque.tail;
+                self.index = self.cur.map(|_| self.deque.size - 1);
+            }
+        }
+    }
+
+    pub fn current(&self) -> Option<&'a T> {
+        self.cur.map(|cur| unsafe { &(*cur.as_ptr()).elem })
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        let next = match self.cur {
+            Some(cur) => unsafe { (*cur.as_ptr()).next },
+            None => self.deque.head,
+        };
+        next.map(|next| unsafe { &(*next.as_ptr()).elem })
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        let prev = match self.cur {
+            Some(cur) => unsafe { (*cur.as_ptr()).prev },
+            None => self.deque.tail,
+        };
+        prev.map(|prev| unsafe { &(*prev.as_ptr()).elem })
+    }
+}
+
+/// A cursor over a [`LinkedDeque`] which can also edit the deque around its position.
+///
+/// Like [`Cursor`], this can point to the ghost non-element between the back and the front of the
+/// deque. All edits are O(1).
+pub struct CursorMut<'a, T, A: Allocator = Global> {
+    cur: Link<T>,
+    index: Option<usize>,
+    deque: &'a mut LinkedDeque<T, A>,
+}
+
+impl<T, A: Allocator> CursorMut<'_, T, A> {
+    /// The index of the element under the cursor, or [`None`] if it is on the ghost element.
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        match self.cur {
+            Some(cur) => unsafe {
+                self.cur = (*cur.as_ptr()).next;
+                self.index = self.cur.and_then(|_| self.index.map(|index| index + 1));
+            },
+            None => {
+                self.cur = self.deque.head;
+                self.index = self.cur.map(|_| 0);
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match self.cur {
+            Some(cur) => unsafe {
+                self.cur = (*cur.as_ptr()).prev;
+                self.index = self.cur.and_then(|_| self.index.map(|index| index - 1));
+            },
+            None => {
+                self.cur = self.deque.tail;
+                self.index = self.cur.map(|_| self.deque.size - 1);
+            }
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.cur.map(|cur| unsafe { &mut (*cur.as_ptr()).elem })
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        self.next()
+            .map(|next| unsafe { &mut (*next.as_ptr()).elem })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        self.prev()
+            .map(|prev| unsafe { &mut (*prev.as_ptr()).elem })
+    }
+
+    /// Returns a read-only cursor at the same position, which cannot outlive this one.
+    pub fn as_cursor(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            cur: self.cur,
+            index: self.index,
+            deque: self.deque,
         }
     }
+
+    /// Inserts `elem` right before the cursor. If the cursor is on the ghost element, this inserts
+    /// at the back of the deque.
+    pub fn insert_before(&mut self, elem: T) {
+        let node = Node::alloc(elem, &self.deque.alloc);
+        unsafe { self.deque.link_chain(self.prev(), self.cur, node, node, 1) };
+        self.index = self.index.map(|index| index + 1);
+    }
+
+    /// Inserts `elem` right after the cursor. If the cursor is on the ghost element, this inserts
+    /// at the front of the deque.
+    pub fn insert_after(&mut self, elem: T) {
+        let node = Node::alloc(elem, &self.deque.alloc);
+        unsafe { self.deque.link_chain(self.cur, self.next(), node, node, 1) };
+    }
+
+    /// Removes the element under the cursor and moves the cursor to the next element. Returns
+    /// [`None`] if the cursor is on the ghost element.
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.cur.map(|cur| {
+            let node = unsafe { self.deque.unlink_node(cur) };
+            self.cur = node.next;
+            self.index = self.cur.and(self.index);
+            node.elem
+        })
+    }
+
+    /// Moves all the elements of `other` right before the cursor, in order. If the cursor is on
+    /// the ghost element, they are appended at the back of the deque.
+    ///
+    /// Like [`LinkedDeque::append`], this is O(1) with a zero-sized allocator and O(n) otherwise.
+    pub fn splice_before(&mut self, mut other: LinkedDeque<T, A>) {
+        if !LinkedDeque::<T, A>::SHARES_NODES {
+            while let Some(elem) = other.pop_front() {
+                self.insert_before(elem);
+            }
+        } else if let Some((first, last, len)) = other.take_chain() {
+            unsafe {
+                self.deque
+                    .link_chain(self.prev(), self.cur, first, last, len)
+            };
+            self.index = self.index.map(|index| index + len);
+        }
+    }
+
+    /// Moves all the elements of `other` right after the cursor, in order. If the cursor is on the
+    /// ghost element, they are prepended at the front of the deque.
+    ///
+    /// Like [`LinkedDeque::append`], this is O(1) with a zero-sized allocator and O(n) otherwise.
+    pub fn splice_after(&mut self, mut other: LinkedDeque<T, A>) {
+        if !LinkedDeque::<T, A>::SHARES_NODES {
+            while let Some(elem) = other.pop_back() {
+                self.insert_after(elem);
+            }
+        } else if let Some((first, last, len)) = other.take_chain() {
+            unsafe {
+                self.deque
+                    .link_chain(self.cur, self.next(), first, last, len)
+            };
+        }
+    }
+
+    fn next(&self) -> Link<T> {
+        match self.cur {
+            Some(cur) => unsafe { (*cur.as_ptr()).next },
+            None => self.deque.head,
+        }
+    }
+
+    fn prev(&self) -> Link<T> {
+        match self.cur {
+            Some(cur) => unsafe { (*cur.as_ptr()).prev },
+            None => self.deque.tail,
+        }
+    }
+}
+
+/// Iterator returned by [`LinkedDeque::extract_if`].
+pub struct ExtractIf<'a, T, F, A: Allocator = Global> {
+    cursor: CursorMut<'a, T, A>,
+    filter: F,
+}
+
+impl<T, F: FnMut(&mut T) -> bool, A: Allocator> Iterator for ExtractIf<'_, T, F, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(elem) = self.cursor.current() {
+            if (self.filter)(elem) {
+                return self.cursor.remove_current();
+            }
+            self.cursor.move_next();
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self
+            .cursor
+            .index
+            .map(|index| self.cursor.deque.size - index);
+        (0, Some(remaining.unwrap_or(0)))
+    }
+}
+
+/// Holds on to every node of a [`LinkedDeque`] while it is being sorted. Each list is only linked
+/// through `next` and ends with [`None`].
+///
+/// Once sorting is done, or if the comparison function panics, dropping this links all the nodes
+/// back into the deque and rebuilds their `prev` links so that no element is lost.
+struct MergeSort<'a, T, A: Allocator> {
+    deque: &'a mut LinkedDeque<T, A>,
+    /// Output of the current pass.
+    sorted: Link<T>,
+    sorted_tail: Link<T>,
+    /// The two runs currently being merged.
+    left: Link<T>,
+    right: Link<T>,
+    /// Input of the current pass which has not been merged yet.
+    unsorted: Link<T>,
+}
+
+impl<T, A: Allocator> Drop for MergeSort<'_, T, A> {
+    fn drop(&mut self) {
+        let mut prev: Link<T> = None;
+
+        for list in [self.sorted, self.left, self.right, self.unsorted] {
+            let mut node = list;
+            while let Some(inner) = node {
+                unsafe {
+                    (*inner.as_ptr()).prev = prev;
+                    match prev {
+                        Some(prev) => (*prev.as_ptr()).next = Some(inner),
+                        None => self.deque.head = Some(inner),
+                    }
+                    node = (*inner.as_ptr()).next;
+                }
+                prev = Some(inner);
+            }
+        }
+
+        self.deque.tail = prev;
+    }
+}
+
+/// Cuts `list` after its first `n` nodes, returning the rest.
+///
+/// # Safety
+///
+/// `list` must be a valid chain of nodes ending with [`None`].
+unsafe fn split_after<T>(list: Link<T>, n: usize) -> Link<T> {
+    let mut node = list?;
+    for _ in 1..n {
+        node = unsafe { (*node.as_ptr()).next? };
+    }
+    unsafe { (*node.as_ptr()).next.take() }
+}
+
+impl<T, A: Allocator> IntoIterator for LinkedDeque<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a LinkedDeque<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut LinkedDeque<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T, A: Allocator = Global>(LinkedDeque<T, A>);
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.size, Some(self.0.size))
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> std::iter::FusedIterator for IntoIter<T, A> {}
+
+/// `head` and `tail` are the next nodes to be yielded from either end, and `size` is the number of
+/// nodes left between them. Checking `size` is what stops both ends from crossing over.
+pub struct Iter<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    size: usize,
+    _boo: std::marker::PhantomData<&'a T>,
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            head: self.head,
+            tail: self.tail,
+            size: self.size,
+            _boo: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            None
+        } else {
+            self.head.map(|head| unsafe {
+                self.head = (*head.as_ptr()).next;
+                self.size -= 1;
+                &(*head.as_ptr()).elem
+            })
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.size, Some(self.size))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            None
+        } else {
+            self.tail.map(|tail| unsafe {
+                self.tail = (*tail.as_ptr()).prev;
+                self.size -= 1;
+                &(*tail.as_ptr()).elem
+            })
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> std::iter::FusedIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    size: usize,
+    _boo: std::marker::PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            None
+        } else {
+            self.head.map(|head| unsafe {
+                self.head = (*head.as_ptr()).next;
+                self.size -= 1;
+                &mut (*head.as_ptr()).elem
+            })
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.size, Some(self.size))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            None
+        } else {
+            self.tail.map(|tail| unsafe {
+                self.tail = (*tail.as_ptr()).prev;
+                self.size -= 1;
+                &mut (*tail.as_ptr()).elem
+            })
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> std::iter::FusedIterator for IterMut<'_, T> {}
+
+impl<T> Node<T> {
+    /// Allocates a detached node with `alloc`. It must later be freed with that same allocator.
+    fn alloc<A: Allocator>(elem: T, alloc: &A) -> std::ptr::NonNull<Self> {
+        let node = Box::new_in(
+            Self {
+                elem,
+                next: None,
+                prev: None,
+                handle: NO_HANDLE,
+                table: 0,
+            },
+            alloc,
+        );
+
+        Box::into_non_null(node).0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dsa_util::{DropCounter, TrackingAllocator};
+
+    use super::*;
+
+    #[test]
+    fn linked_deque_new() {
+        let deque = LinkedDeque::<()>::new();
+        assert_eq!(deque.head, None);
+        assert_eq!(deque.tail, None);
+        assert_eq!(deque.len(), 0);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_push_back_pop_front() {
+        let mut deque = LinkedDeque::new();
+        assert_eq!(deque.pop_front(), None);
+
+        for n in 0..10 {
+            deque.push_back(n);
+        }
+        assert_eq!(deque.len(), 10);
+
+        for n in 0..10 {
+            assert_eq!(deque.pop_front(), Some(n));
+        }
+
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.head, None);
+        assert_eq!(deque.tail, None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_push_back_pop_back() {
+        let mut deque = LinkedDeque::new();
+        assert_eq!(deque.pop_back(), None);
+
+        for n in 0..10 {
+            deque.push_back(n);
+        }
+        assert_eq!(deque.len(), 10);
+
+        for n in (0..10).rev() {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+
+        assert_eq!(deque.pop_back(), None);
+        assert_eq!(deque.head, None);
+        assert_eq!(deque.tail, None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_push_front_pop_front() {
+        let mut deque = LinkedDeque::new();
+        for n in 0..10 {
+            deque.push_front(n);
+        }
+        assert_eq!(deque.len(), 10);
+
+        for n in (0..10).rev() {
+            assert_eq!(deque.pop_front(), Some(n));
+        }
+
+        assert_eq!(deque.pop_front(), None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_push_front_pop_back() {
+        let mut deque = LinkedDeque::new();
+        for n in 0..10 {
+            deque.push_front(n);
+        }
+        assert_eq!(deque.len(), 10);
+
+        for n in 0..10 {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+
+        assert_eq!(deque.pop_back(), None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_mixed() {
+        let mut deque = LinkedDeque::new();
+
+        // A single element is both the head and the tail
+        deque.push_back(1);
+        assert_eq!(deque.head, deque.tail);
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), None);
+
+        deque.push_front(1);
+        deque.push_back(2);
+        deque.push_front(0);
+        deque.push_back(3);
+        assert_eq!(deque.len(), 4);
+
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(2));
+        assert_eq!(deque.pop_back(), Some(1));
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.len(), 0);
+
+        // The deque should still be usable once emptied
+        deque.push_back(4);
+        assert_eq!(deque.peek_front(), Some(&4));
+        assert_eq!(deque.peek_back(), Some(&4));
+    }
+
+    #[test]
+    fn linked_deque_peek_front() {
+        let mut deque = LinkedDeque::new();
+        assert_eq!(deque.peek_front(), None);
+
+        for n in 0..10 {
+            deque.push_front(n);
+            assert_eq!(deque.peek_front(), Some(&n));
+            assert_eq!(deque.peek_back(), Some(&0));
+        }
+    }
+
+    #[test]
+    fn linked_deque_peek_back() {
+        let mut deque = LinkedDeque::new();
+        assert_eq!(deque.peek_back(), None);
+
+        for n in 0..10 {
+            deque.push_back(n);
+            assert_eq!(deque.peek_back(), Some(&n));
+            assert_eq!(deque.peek_front(), Some(&0));
+        }
+    }
+
+    #[test]
+    fn linked_deque_peek_front_mut() {
+        let mut deque = LinkedDeque::new();
+        assert_eq!(deque.peek_front_mut(), None);
+
+        for n in 0..10 {
+            deque.push_front(n);
+            *deque.peek_front_mut().unwrap() += 1;
+            assert_eq!(deque.peek_front(), Some(&(n + 1)));
+        }
+    }
+
+    #[test]
+    fn linked_deque_peek_back_mut() {
+        let mut deque = LinkedDeque::new();
+        assert_eq!(deque.peek_back_mut(), None);
+
+        for n in 0..10 {
+            deque.push_back(n);
+            *deque.peek_back_mut().unwrap() += 1;
+            assert_eq!(deque.peek_back(), Some(&(n + 1)));
+        }
+    }
+
+    #[test]
+    fn linked_deque_drop() {
+        let mut deque = LinkedDeque::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..10 {
+            if n % 2 == 0 {
+                deque.push_back(DropCounter::new(&rc, vec![n]));
+            } else {
+                deque.push_front(DropCounter::new(&rc, vec![n]));
+            }
+        }
+
+        drop(deque);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_deque_drop_partial() {
+        let mut deque = LinkedDeque::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..10 {
+            deque.push_back(DropCounter::new(&rc, vec![n]));
+        }
+
+        drop(deque.pop_front());
+        drop(deque.pop_back());
+        assert_eq!(rc.get(), 2);
+
+        drop(deque);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_deque_drop_long() {
+        let mut deque = LinkedDeque::new();
+        for n in 0..100_000 {
+            deque.push_back(n);
+        }
+
+        // This would overflow the stack if nodes were dropped recursively
+        drop(deque);
+    }
+
+    fn to_vec<T: Clone, A: Allocator>(deque: &LinkedDeque<T, A>) -> Vec<T> {
+        deque.iter().cloned().collect()
+    }
+
+    #[test]
+    fn linked_deque_cursor_walk() {
+        let mut deque = LinkedDeque::new();
+        for n in 0..5 {
+            deque.push_back(n);
+        }
+
+        let mut cursor = deque.cursor_front();
+        for n in 0..5 {
+            assert_eq!(cursor.index(), Some(n));
+            assert_eq!(cursor.current(), Some(&(n as i32)));
+            cursor.move_next();
+        }
+
+        // We should now be on the ghost element
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&0));
+        assert_eq!(cursor.peek_prev(), Some(&4));
+
+        // Moving past the ghost wraps around
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&0));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&4));
+        assert_eq!(cursor.index(), Some(4));
+
+        let mut cursor = deque.cursor_back();
+        for n in (0..5).rev() {
+            assert_eq!(cursor.index(), Some(n));
+            assert_eq!(cursor.current(), Some(&(n as i32)));
+            cursor.move_prev();
+        }
+        assert_eq!(cursor.index(), None);
+    }
+
+    #[test]
+    fn linked_deque_cursor_empty() {
+        let mut deque = LinkedDeque::<i32>::new();
+
+        let mut cursor = deque.cursor_front();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), None);
+        assert_eq!(cursor.peek_prev(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+
+        let mut cursor = deque.cursor_back_mut();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.remove_current(), None);
+        cursor.insert_after(0);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 0));
+
+        assert_eq!(to_vec(&deque), [0]);
+        assert_eq!(deque.peek_back(), Some(&0));
+    }
+
+    #[test]
+    fn linked_deque_cursor_mut_current() {
+        let mut deque = LinkedDeque::new();
+        for n in 0..5 {
+            deque.push_back(n);
+        }
+
+        let mut cursor = deque.cursor_front_mut();
+        while let Some(elem) = cursor.current() {
+            *elem *= 10;
+            cursor.move_next();
+        }
+
+        *cursor.peek_next().unwrap() += 1;
+        *cursor.peek_prev().unwrap() += 1;
+        assert_eq!(cursor.as_cursor().peek_next(), Some(&1));
+
+        assert_eq!(to_vec(&deque), [1, 10, 20, 30, 41]);
+    }
+
+    #[test]
+    fn linked_deque_cursor_mut_insert() {
+        let mut deque = LinkedDeque::new();
+        deque.push_back(1);
+        deque.push_back(3);
+
+        let mut cursor = deque.cursor_front_mut();
+        cursor.insert_before(0);
+        assert_eq!(cursor.index(), Some(1));
+        cursor.insert_after(2);
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 1));
+
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 3));
+        cursor.insert_after(4);
+
+        // Inserting around the ghost element works on the ends of the deque
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        cursor.insert_before(5);
+        cursor.insert_after(-1);
+        assert_eq!(cursor.index(), None);
+
+        assert_eq!(deque.len(), 7);
+        assert_eq!(to_vec(&deque), [-1, 0, 1, 2, 3, 4, 5]);
+        assert_eq!(deque.peek_front(), Some(&-1));
+        assert_eq!(deque.peek_back(), Some(&5));
+
+        for n in (-1..6).rev() {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_cursor_mut_remove() {
+        let mut deque = LinkedDeque::new();
+        for n in 0..5 {
+            deque.push_back(n);
+        }
+
+        let mut cursor = deque.cursor_front_mut();
+        assert_eq!(cursor.remove_current(), Some(0));
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.current(), Some(&mut 1));
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 3));
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.remove_current(), None);
+
+        assert_eq!(deque.len(), 2);
+        assert_eq!(to_vec(&deque), [1, 3]);
+        assert_eq!(deque.peek_back(), Some(&3));
+
+        let mut cursor = deque.cursor_back_mut();
+        assert_eq!(cursor.remove_current(), Some(3));
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.remove_current(), None);
+
+        assert!(deque.is_empty());
+        assert_eq!(deque.head, None);
+        assert_eq!(deque.tail, None);
+    }
+
+    #[test]
+    fn linked_deque_cursor_mut_splice() {
+        let mut deque = LinkedDeque::new();
+        deque.push_back(0);
+        deque.push_back(5);
+
+        let mut other = LinkedDeque::new();
+        for n in 1..3 {
+            other.push_back(n);
+        }
+
+        let mut cursor = deque.cursor_back_mut();
+        cursor.splice_before(other);
+        assert_eq!(cursor.index(), Some(3));
+        assert_eq!(cursor.current(), Some(&mut 5));
+
+        let mut other = LinkedDeque::new();
+        for n in 3..5 {
+            other.push_back(n);
+        }
+
+        cursor.move_prev();
+        cursor.splice_after(other);
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.current(), Some(&mut 2));
+
+        // Splicing on the ghost element works on the ends of the deque
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+
+        let mut other = LinkedDeque::new();
+        other.push_back(6);
+        other.push_back(7);
+        cursor.splice_before(other);
+
+        let mut other = LinkedDeque::new();
+        other.push_back(-2);
+        other.push_back(-1);
+        cursor.splice_after(other);
+
+        cursor.splice_after(LinkedDeque::new());
+        cursor.splice_before(LinkedDeque::new());
+        assert_eq!(cursor.index(), None);
+
+        assert_eq!(deque.len(), 10);
+        assert_eq!(to_vec(&deque), [-2, -1, 0, 1, 2, 3, 4, 5, 6, 7]);
+
+        for n in (-2..8).rev() {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_cursor_mut_splice_empty() {
+        let mut deque = LinkedDeque::new();
+        let mut other = LinkedDeque::new();
+        other.push_back(0);
+        other.push_back(1);
+
+        let mut cursor = deque.cursor_front_mut();
+        cursor.splice_after(other);
+
+        assert_eq!(deque.len(), 2);
+        assert_eq!(deque.peek_front(), Some(&0));
+        assert_eq!(deque.peek_back(), Some(&1));
+    }
+
+    #[test]
+    fn linked_deque_cursor_mut_drop() {
+        let mut deque = LinkedDeque::new();
+        let mut other = LinkedDeque::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..5 {
+            deque.push_back(DropCounter::new(&rc, vec![n]));
+            other.push_back(DropCounter::new(&rc, vec![n]));
+        }
+
+        let mut cursor = deque.cursor_front_mut();
+        cursor.move_next();
+        drop(cursor.remove_current());
+        assert_eq!(rc.get(), 1);
+
+        cursor.insert_before(DropCounter::new(&rc, vec![10]));
+        cursor.insert_after(DropCounter::new(&rc, vec![11]));
+        cursor.splice_after(other);
+        assert_eq!(rc.get(), 1);
+        assert_eq!(deque.len(), 11);
+
+        drop(deque);
+        assert_eq!(rc.get(), 12);
+    }
+
+    #[test]
+    fn linked_deque_append() {
+        let mut deque = LinkedDeque::new();
+        let mut other = LinkedDeque::new();
+        for n in 0..5 {
+            deque.push_back(n);
+            other.push_back(n + 5);
+        }
+
+        deque.append(&mut other);
+        assert_eq!(deque.len(), 10);
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(deque.peek_back(), Some(&9));
+
+        assert!(other.is_empty());
+        assert_eq!(other.head, None);
+        assert_eq!(other.tail, None);
+
+        // The tail should be correctly linked back to the rest of the deque
+        for n in (0..10).rev() {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_append_edge_cases() {
+        let mut deque = LinkedDeque::new();
+        let mut other = LinkedDeque::new();
+
+        // empty + empty
+        deque.append(&mut other);
+        assert!(deque.is_empty());
+        assert_eq!(deque.head, None);
+        assert_eq!(deque.tail, None);
+
+        // empty + one
+        other.push_back(0);
+        deque.append(&mut other);
+        assert_eq!(deque.len(), 1);
+        assert_eq!(deque.head, deque.tail);
+        assert_eq!(deque.peek_back(), Some(&0));
+
+        // one + empty
+        deque.append(&mut other);
+        assert_eq!(deque.len(), 1);
+        assert_eq!(deque.head, deque.tail);
+
+        // one + one
+        other.push_back(1);
+        deque.append(&mut other);
+        assert_eq!(deque.len(), 2);
+        assert_eq!(deque.peek_front(), Some(&0));
+        assert_eq!(deque.peek_back(), Some(&1));
+        assert_eq!(deque.pop_back(), Some(1));
+        assert_eq!(deque.pop_back(), Some(0));
+        assert_eq!(deque.pop_back(), None);
+    }
+
+    #[test]
+    fn linked_deque_prepend() {
+        let mut deque = LinkedDeque::new();
+        let mut other = LinkedDeque::new();
+        for n in 0..5 {
+            deque.push_back(n + 5);
+            other.push_back(n);
+        }
+
+        deque.prepend(&mut other);
+        assert_eq!(deque.len(), 10);
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(other.is_empty());
+
+        for n in (0..10).rev() {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_prepend_edge_cases() {
+        let mut deque = LinkedDeque::new();
+        let mut other = LinkedDeque::new();
+
+        // empty + empty
+        deque.prepend(&mut other);
+        assert!(deque.is_empty());
+        assert_eq!(deque.head, None);
+        assert_eq!(deque.tail, None);
+
+        // one + empty
+        other.push_back(1);
+        deque.prepend(&mut other);
+        assert_eq!(deque.len(), 1);
+        assert_eq!(deque.head, deque.tail);
+
+        // one + one
+        other.push_back(0);
+        deque.prepend(&mut other);
+        assert_eq!(deque.len(), 2);
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.tail, None);
+    }
+
+    #[test]
+    fn linked_deque_split_off() {
+        for at in 0..=10 {
+            let mut deque = LinkedDeque::new();
+            for n in 0..10 {
+                deque.push_back(n);
+            }
+
+            let mut other = deque.split_off(at);
+            assert_eq!(deque.len(), at);
+            assert_eq!(other.len(), 10 - at);
+            assert_eq!(to_vec(&deque), (0..at as i32).collect::<Vec<_>>());
+            assert_eq!(to_vec(&other), (at as i32..10).collect::<Vec<_>>());
+
+            // Both halves should have their ends correctly unlinked from each other
+            for n in (0..at as i32).rev() {
+                assert_eq!(deque.pop_back(), Some(n));
+            }
+            assert_eq!(deque.pop_back(), None);
+            assert_eq!(deque.head, None);
+
+            for n in at as i32..10 {
+                assert_eq!(other.pop_front(), Some(n));
+            }
+            assert_eq!(other.pop_front(), None);
+            assert_eq!(other.tail, None);
+        }
+    }
+
+    #[test]
+    fn linked_deque_split_off_edge_cases() {
+        let mut deque = LinkedDeque::<i32>::new();
+        let other = deque.split_off(0);
+        assert!(deque.is_empty());
+        assert!(other.is_empty());
+
+        deque.push_back(0);
+        let other = deque.split_off(1);
+        assert_eq!(deque.len(), 1);
+        assert_eq!(deque.head, deque.tail);
+        assert!(other.is_empty());
+
+        let mut other = deque.split_off(0);
+        assert!(deque.is_empty());
+        assert_eq!(deque.head, None);
+        assert_eq!(deque.tail, None);
+        assert_eq!(other.len(), 1);
+        assert_eq!(other.head, other.tail);
+
+        // The split halves can be joined back together
+        deque.append(&mut other);
+        assert_eq!(deque.len(), 1);
+        assert_eq!(deque.pop_back(), Some(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn linked_deque_split_off_panic_out_of_bounds() {
+        let mut deque = LinkedDeque::new();
+        deque.push_back(0);
+        let _ = deque.split_off(2);
+    }
+
+    #[test]
+    fn linked_deque_append_split_off_drop() {
+        let mut deque = LinkedDeque::new();
+        let mut other = LinkedDeque::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..5 {
+            deque.push_back(DropCounter::new(&rc, vec![n]));
+            other.push_back(DropCounter::new(&rc, vec![n]));
+        }
+
+        deque.append(&mut other);
+        drop(other);
+        assert_eq!(rc.get(), 0);
+
+        let other = deque.split_off(3);
+        drop(other);
+        assert_eq!(rc.get(), 7);
+
+        drop(deque);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_deque_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<LinkedDeque<i32>>();
+        assert_send_sync::<Iter<'_, i32>>();
+        assert_send_sync::<IterMut<'_, i32>>();
+        assert_send_sync::<IntoIter<i32>>();
+        assert_send_sync::<Cursor<'_, i32>>();
+        assert_send_sync::<CursorMut<'_, i32>>();
+    }
+
+    #[test]
+    fn linked_deque_iter_forwards() {
+        let deque = (0..10).collect::<LinkedDeque<_>>();
+
+        let mut iter = deque.iter();
+        for n in 0..10 {
+            assert_eq!(iter.len(), 10 - n);
+            assert_eq!(iter.next(), Some(&(n as i32)));
+        }
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+
+        // iter should not mutate the base deque
+        assert_eq!(deque.len(), 10);
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn linked_deque_iter_reversed() {
+        let deque = (0..10).collect::<LinkedDeque<_>>();
+
+        let mut iter = deque.iter().rev();
+        for n in (0..10).rev() {
+            assert_eq!(iter.next(), Some(&n));
+        }
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn linked_deque_iter_both_ends() {
+        let deque = (0..5).collect::<LinkedDeque<_>>();
+
+        let mut iter = deque.iter();
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next_back(), Some(&2));
+
+        // Both ends have met, so neither should yield anything more
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn linked_deque_iter_mut() {
+        let mut deque = (0..10).collect::<LinkedDeque<_>>();
+
+        for elem in deque.iter_mut() {
+            *elem += 1;
+        }
+
+        let mut iter = deque.iter_mut();
+        assert_eq!(iter.len(), 10);
+        *iter.next_back().unwrap() *= 10;
+        *iter.next().unwrap() *= 10;
+        assert_eq!(iter.len(), 8);
+
+        assert_eq!(to_vec(&deque), [10, 2, 3, 4, 5, 6, 7, 8, 9, 100]);
+    }
+
+    #[test]
+    fn linked_deque_iter_ref() {
+        let mut deque = (0..10).collect::<LinkedDeque<_>>();
+
+        for elem in &mut deque {
+            *elem *= 2;
+        }
+
+        let mut n = 0;
+        for elem in &deque {
+            assert_eq!(*elem, n);
+            n += 2;
+        }
+    }
+
+    #[test]
+    fn linked_deque_into_iter() {
+        let deque = (0..10).collect::<LinkedDeque<_>>();
+
+        let mut iter = deque.into_iter();
+        for n in 0..5 {
+            assert_eq!(iter.next(), Some(n));
+            assert_eq!(iter.next_back(), Some(9 - n));
+        }
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn linked_deque_into_iter_drop() {
+        let mut deque = LinkedDeque::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..10 {
+            deque.push_back(DropCounter::new(&rc, vec![n]));
+        }
+
+        let mut iter = deque.into_iter();
+        drop(iter.next());
+        drop(iter.next_back());
+        assert_eq!(rc.get(), 2);
+
+        drop(iter);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_deque_from_iter() {
+        let deque = (0..10).collect::<LinkedDeque<_>>();
+        assert_eq!(deque.len(), 10);
+        assert_eq!(deque.peek_front(), Some(&0));
+        assert_eq!(deque.peek_back(), Some(&9));
+
+        let deque = std::iter::empty::<i32>().collect::<LinkedDeque<_>>();
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_extend() {
+        let mut deque = (0..5).collect::<LinkedDeque<_>>();
+        deque.extend(5..8);
+        deque.extend(&[8, 9]);
+
+        assert_eq!(deque.len(), 10);
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn linked_deque_debug() {
+        let deque = (0..10).collect::<LinkedDeque<_>>();
+        assert_eq!(&format!("{deque:?}"), "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
+    }
+
+    #[test]
+    fn linked_deque_clone() {
+        let deque = (0..10).collect::<LinkedDeque<_>>();
+        let mut deque_clone = deque.clone();
+
+        for elem in deque_clone.iter_mut() {
+            *elem += 1;
+        }
+
+        assert_eq!(deque.len(), 10);
+        assert_eq!(deque_clone.len(), 10);
+        assert_eq!(to_vec(&deque), (0..10).collect::<Vec<_>>());
+        assert_eq!(to_vec(&deque_clone), (1..11).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn linked_deque_eq() {
+        let deque_1 = (0..10).collect::<LinkedDeque<_>>();
+        let deque_2 = (0..10).collect::<LinkedDeque<_>>();
+        let deque_3 = (0..9).collect::<LinkedDeque<_>>();
+        let deque_4 = (1..11).collect::<LinkedDeque<_>>();
+
+        assert_eq!(deque_1, deque_2);
+        assert_ne!(deque_1, deque_3);
+        assert_ne!(deque_1, deque_4);
+        assert_eq!(LinkedDeque::<i32>::new(), LinkedDeque::default());
+    }
+
+    #[test]
+    fn linked_deque_hash() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let state = RandomState::new();
+        let deque_1 = (0..10).collect::<LinkedDeque<_>>();
+        let deque_2 = (0..10).collect::<LinkedDeque<_>>();
+
+        assert_eq!(state.hash_one(&deque_1), state.hash_one(&deque_2));
+
+        let nested_1 = [vec![0, 1], vec![2]]
+            .into_iter()
+            .map(LinkedDeque::from_iter)
+            .collect::<LinkedDeque<_>>();
+        let nested_2 = [vec![0], vec![1, 2]]
+            .into_iter()
+            .map(LinkedDeque::from_iter)
+            .collect::<LinkedDeque<_>>();
+
+        assert_ne!(state.hash_one(&nested_1), state.hash_one(&nested_2));
+    }
+
+    #[test]
+    fn linked_deque_handle_get() {
+        let mut deque = LinkedDeque::new();
+        let handles = (0..10)
+            .map(|n| {
+                if n % 2 == 0 {
+                    deque.push_back_handle(n)
+                } else {
+                    deque.push_front_handle(n)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(deque.len(), 10);
+        assert_eq!(to_vec(&deque), [9, 7, 5, 3, 1, 0, 2, 4, 6, 8]);
+
+        for (n, handle) in handles.iter().enumerate() {
+            assert_eq!(deque.get(*handle), Some(&n));
+            *deque.get_mut(*handle).unwrap() += 10;
+            assert_eq!(deque.get(*handle), Some(&(n + 10)));
+        }
+    }
+
+    #[test]
+    fn linked_deque_handle_remove() {
+        let mut deque = LinkedDeque::new();
+        let handles = (0..5)
+            .map(|n| deque.push_back_handle(n))
+            .collect::<Vec<_>>();
+
+        assert_eq!(deque.remove(handles[2]), Some(2));
+        assert_eq!(deque.remove(handles[0]), Some(0));
+        assert_eq!(deque.remove(handles[4]), Some(4));
+        assert_eq!(deque.len(), 2);
+        assert_eq!(to_vec(&deque), [1, 3]);
+        assert_eq!(deque.peek_front(), Some(&1));
+        assert_eq!(deque.peek_back(), Some(&3));
+
+        // Removed handles are stale
+        assert_eq!(deque.remove(handles[2]), None);
+        assert_eq!(deque.get(handles[0]), None);
+        assert_eq!(deque.get_mut(handles[4]), None);
+        assert_eq!(deque.move_to_front(handles[4]), None);
+        assert_eq!(deque.len(), 2);
+
+        assert_eq!(deque.remove(handles[1]), Some(1));
+        assert_eq!(deque.remove(handles[3]), Some(3));
+        assert!(deque.is_empty());
+        assert_eq!(deque.head, None);
+        assert_eq!(deque.tail, None);
+    }
+
+    #[test]
+    fn linked_deque_handle_stale_after_pop() {
+        let mut deque = LinkedDeque::new();
+        let front = deque.push_back_handle(0);
+        let back = deque.push_back_handle(1);
+
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(1));
+        assert_eq!(deque.get(front), None);
+        assert_eq!(deque.get(back), None);
+
+        let mut cursor = deque.cursor_front_mut();
+        cursor.insert_after(2);
+        let handle = deque.push_back_handle(3);
+        let mut cursor = deque.cursor_back_mut();
+        assert_eq!(cursor.remove_current(), Some(3));
+        assert_eq!(deque.get(handle), None);
+    }
+
+    #[test]
+    fn linked_deque_handle_slot_reuse() {
+        let mut deque = LinkedDeque::new();
+        let old = deque.push_back_handle(0);
+        deque.remove(old);
+
+        // The new handle reuses the slot of the old one, but with a different generation
+        let new = deque.push_back_handle(1);
+        assert_eq!(old.index, new.index);
+        assert_ne!(old.generation, new.generation);
+
+        assert_eq!(deque.get(old), None);
+        assert_eq!(deque.remove(old), None);
+        assert_eq!(deque.get(new), Some(&1));
+        assert_eq!(deque.handles.len(), 1);
+    }
+
+    #[test]
+    fn linked_deque_handle_move() {
+        let mut deque = LinkedDeque::new();
+        let handles = (0..5)
+            .map(|n| deque.push_back_handle(n))
+            .collect::<Vec<_>>();
+
+        assert_eq!(deque.move_to_front(handles[2]), Some(&mut 2));
+        assert_eq!(to_vec(&deque), [2, 0, 1, 3, 4]);
+        assert_eq!(deque.move_to_front(handles[2]), Some(&mut 2));
+        assert_eq!(to_vec(&deque), [2, 0, 1, 3, 4]);
+        assert_eq!(deque.move_to_front(handles[4]), Some(&mut 4));
+        assert_eq!(to_vec(&deque), [4, 2, 0, 1, 3]);
+
+        assert_eq!(deque.move_to_back(handles[4]), Some(&mut 4));
+        assert_eq!(to_vec(&deque), [2, 0, 1, 3, 4]);
+        assert_eq!(deque.move_to_back(handles[0]), Some(&mut 0));
+        assert_eq!(to_vec(&deque), [2, 1, 3, 4, 0]);
+
+        // Handles stay valid after moving
+        for (n, handle) in handles.iter().enumerate() {
+            assert_eq!(deque.get(*handle), Some(&n));
+        }
+        assert_eq!(deque.len(), 5);
+
+        // The links should be correct when walking backwards too
+        for n in [0, 4, 3, 1, 2] {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+        assert!(deque.is_empty());
+
+        let handle = deque.push_back_handle(0);
+        assert_eq!(deque.move_to_front(handle), Some(&mut 0));
+        assert_eq!(deque.move_to_back(handle), Some(&mut 0));
+        assert_eq!(deque.head, deque.tail);
+        assert_eq!(deque.len(), 1);
+    }
+
+    #[test]
+    fn linked_deque_handle_split_off() {
+        let mut deque = LinkedDeque::new();
+        let handles = (0..10)
+            .map(|n| deque.push_back_handle(n))
+            .collect::<Vec<_>>();
+
+        let other = deque.split_off(5);
+        for (n, handle) in handles.iter().enumerate() {
+            if n < 5 {
+                assert_eq!(deque.get(*handle), Some(&n));
+            } else {
+                assert_eq!(deque.get(*handle), None);
+            }
+        }
+        assert_eq!(to_vec(&other), [5, 6, 7, 8, 9]);
+
+        let other = deque.split_off(0);
+        for handle in handles.iter() {
+            assert_eq!(deque.get(*handle), None);
+            assert_eq!(other.get(*handle), None);
+        }
+        assert_eq!(to_vec(&other), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn linked_deque_handle_append() {
+        let mut deque = LinkedDeque::new();
+        let mut other = LinkedDeque::new();
+
+        let handle = deque.push_back_handle(0);
+        let handle_other = other.push_back_handle(1);
+
+        deque.append(&mut other);
+        assert_eq!(deque.get(handle), Some(&0));
+        assert_eq!(deque.get(handle_other), None);
+        assert_eq!(other.get(handle_other), None);
+        assert_eq!(to_vec(&deque), [0, 1]);
+        assert!(other.handles.is_empty());
+
+        // Handles to `other` stay stale once it is reused
+        let handle_new = other.push_back_handle(2);
+        assert_eq!(other.get(handle_other), None);
+        assert_eq!(other.get(handle_new), Some(&2));
+
+        let mut cursor = deque.cursor_front_mut();
+        cursor.splice_after(other);
+        assert_eq!(to_vec(&deque), [0, 2, 1]);
+
+        // Moved nodes had a handle in the same slot as `handle`, which must not be freed by them
+        assert_eq!(deque.pop_back(), Some(1));
+        assert_eq!(deque.pop_back(), Some(2));
+        assert_eq!(deque.get(handle), Some(&0));
+    }
+
+    #[test]
+    fn linked_deque_handle_drop() {
+        let mut deque = LinkedDeque::new();
+        let rc = std::rc::Rc::default();
+
+        let handles = (0..10)
+            .map(|n| deque.push_back_handle(DropCounter::new(&rc, vec![n])))
+            .collect::<Vec<_>>();
+
+        drop(deque.remove(handles[3]));
+        assert!(deque.remove(handles[3]).is_none());
+        deque.move_to_front(handles[5]);
+        assert_eq!(rc.get(), 1);
+
+        drop(deque);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_deque_sort() {
+        let mut deque = [5, 3, 8, 0, 9, 1, 7, 2, 6, 4]
+            .into_iter()
+            .collect::<LinkedDeque<_>>();
+
+        deque.sort();
+        assert_eq!(deque.len(), 10);
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        // prev links and the tail should have been rebuilt as well
+        for n in (0..10).rev() {
+            assert_eq!(deque.pop_back(), Some(n));
+        }
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn linked_deque_sort_edge_cases() {
+        let mut deque = LinkedDeque::<i32>::new();
+        deque.sort();
+        assert!(deque.is_empty());
+        assert_eq!(deque.tail, None);
+
+        deque.push_back(0);
+        deque.sort();
+        assert_eq!(deque.len(), 1);
+        assert_eq!(deque.head, deque.tail);
+
+        for size in 2..40 {
+            let mut deque = (0..size)
+                .map(|n| (size + size / 2 - 1 - n) % size)
+                .collect::<LinkedDeque<_>>();
+
+            deque.sort();
+            assert_eq!(deque.len(), size);
+            assert_eq!(to_vec(&deque), (0..size).collect::<Vec<_>>());
+            assert_eq!(
+                deque.iter().rev().copied().collect::<Vec<_>>(),
+                (0..size).rev().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn linked_deque_sort_by_stable() {
+        let mut deque = (0..20).map(|n| (n % 3, n)).collect::<LinkedDeque<_>>();
+        deque.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // Elements with equal keys keep their relative order
+        let mut expected = (0..20).map(|n| (n % 3, n)).collect::<Vec<_>>();
+        expected.sort_by_key(|(k, _)| *k);
+        assert_eq!(to_vec(&deque), expected);
+    }
+
+    #[test]
+    fn linked_deque_sort_by_key() {
+        let mut deque = (0..10).collect::<LinkedDeque<_>>();
+        deque.sort_by_key(|n| std::cmp::Reverse(*n));
+        assert_eq!(to_vec(&deque), [9, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn linked_deque_sort_long() {
+        let mut deque = (0..100_000).rev().collect::<LinkedDeque<_>>();
+
+        deque.sort();
+        assert_eq!(deque.len(), 100_000);
+        for (i, n) in deque.iter().enumerate() {
+            assert_eq!(i, *n);
+        }
+        assert_eq!(deque.peek_back(), Some(&99_999));
+    }
+
+    #[test]
+    fn linked_deque_sort_handles() {
+        let mut deque = LinkedDeque::new();
+        let handles = [3, 1, 2, 0]
+            .into_iter()
+            .map(|n| deque.push_back_handle(n))
+            .collect::<Vec<_>>();
+
+        deque.sort();
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3]);
+
+        // Nodes are only relinked, so handles still point to the same elements
+        assert_eq!(deque.get(handles[0]), Some(&3));
+        assert_eq!(deque.remove(handles[1]), Some(1));
+        assert_eq!(to_vec(&deque), [0, 2, 3]);
+    }
+
+    #[test]
+    fn linked_deque_sort_drop() {
+        let rc = std::rc::Rc::default();
+        let mut deque = (0..10)
+            .rev()
+            .map(|n| (n, DropCounter::new(&rc, vec![n])))
+            .collect::<LinkedDeque<_>>();
+
+        deque.sort_by_key(|(n, _)| *n);
+        assert_eq!(rc.get(), 0);
+        assert_eq!(deque.len(), 10);
+
+        drop(deque);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_deque_sort_panic() {
+        for panic_at in [0, 1, 5, 10, 15] {
+            let rc = std::rc::Rc::default();
+            let mut deque = [5, 3, 8, 0, 9, 1, 7, 2, 6, 4]
+                .into_iter()
+                .map(|n| (n, DropCounter::new(&rc, vec![n])))
+                .collect::<LinkedDeque<_>>();
+
+            let mut comparisons = 0;
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                deque.sort_by(|a, b| {
+                    if comparisons == panic_at {
+                        panic!("Comparator panicked");
+                    }
+                    comparisons += 1;
+                    a.0.cmp(&b.0)
+                })
+            }));
+            assert!(res.is_err());
+
+            // No element should have been dropped or duplicated, and links should be consistent
+            assert_eq!(rc.get(), 0);
+            assert_eq!(deque.len(), 10);
+            let mut elems = deque.iter().map(|(n, _)| *n).collect::<Vec<_>>();
+            let mut elems_rev = deque.iter().rev().map(|(n, _)| *n).collect::<Vec<_>>();
+            elems_rev.reverse();
+            assert_eq!(elems, elems_rev);
+            elems.sort();
+            assert_eq!(elems, (0..10).collect::<Vec<_>>());
+
+            deque.sort_by_key(|(n, _)| *n);
+            assert_eq!(deque.peek_front().map(|(n, _)| *n), Some(0));
+            assert_eq!(deque.peek_back().map(|(n, _)| *n), Some(9));
+
+            drop(deque);
+            assert_eq!(rc.get(), 10);
+        }
+    }
+
+    #[test]
+    fn linked_deque_retain() {
+        let mut deque = (0..10).collect::<LinkedDeque<_>>();
+
+        deque.retain(|n| n % 2 == 0);
+        assert_eq!(deque.len(), 5);
+        assert_eq!(to_vec(&deque), [0, 2, 4, 6, 8]);
+        assert_eq!(deque.peek_back(), Some(&8));
+
+        deque.retain_mut(|n| {
+            *n += 1;
+            *n > 2
+        });
+        assert_eq!(to_vec(&deque), [3, 5, 7, 9]);
+        assert_eq!(
+            deque.iter().rev().copied().collect::<Vec<_>>(),
+            [9, 7, 5, 3]
+        );
+
+        deque.retain(|_| false);
+        assert!(deque.is_empty());
+        assert_eq!(deque.head, None);
+        assert_eq!(deque.tail, None);
+    }
+
+    #[test]
+    fn linked_deque_retain_panic() {
+        let rc = std::rc::Rc::default();
+        let mut deque = (0..10)
+            .map(|n| (n, DropCounter::new(&rc, vec![n])))
+            .collect::<LinkedDeque<_>>();
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            deque.retain(|(n, _)| {
+                assert_ne!(*n, 4);
+                n % 2 == 0
+            })
+        }));
+        assert!(res.is_err());
+
+        assert_eq!(rc.get(), 2);
+        assert_eq!(deque.len(), 8);
+        assert_eq!(
+            deque.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            [0, 2, 4, 5, 6, 7, 8, 9]
+        );
+
+        drop(deque);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_deque_extract_if() {
+        let mut deque = (0..10).collect::<LinkedDeque<_>>();
+
+        let mut iter = deque.extract_if(|n| *n % 3 == 0);
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next(), Some(6));
+        assert_eq!(iter.next(), Some(9));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(deque.len(), 6);
+        assert_eq!(to_vec(&deque), [1, 2, 4, 5, 7, 8]);
+        assert_eq!(deque.peek_back(), Some(&8));
+    }
+
+    #[test]
+    fn linked_deque_extract_if_lazy() {
+        let mut deque = (0..10).collect::<LinkedDeque<_>>();
+
+        // Dropping the iterator early leaves the remaining elements in place
+        {
+            let mut iter = deque.extract_if(|n| *n % 2 == 1);
+            assert_eq!(iter.next(), Some(1));
+            assert_eq!(iter.size_hint(), (0, Some(8)));
+            assert_eq!(iter.next(), Some(3));
+        }
+
+        assert_eq!(deque.len(), 8);
+        assert_eq!(to_vec(&deque), [0, 2, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn linked_deque_extract_if_panic() {
+        let rc = std::rc::Rc::default();
+        let mut deque = (0..10)
+            .map(|n| (n, DropCounter::new(&rc, vec![n])))
+            .collect::<LinkedDeque<_>>();
+
+        let mut extracted = Vec::new();
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            for elem in deque.extract_if(|(n, _)| {
+                assert_ne!(*n, 6);
+                *n % 2 == 1
+            }) {
+                extracted.push(elem);
+            }
+        }));
+        assert!(res.is_err());
+
+        assert_eq!(rc.get(), 0);
+        assert_eq!(extracted.len(), 3);
+        assert_eq!(deque.len(), 7);
+        assert_eq!(
+            deque.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            [0, 2, 4, 6, 7, 8, 9]
+        );
+
+        drop(extracted);
+        drop(deque);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_deque_remove_first() {
+        let mut deque = (0..10).collect::<LinkedDeque<_>>();
+
+        assert_eq!(deque.remove_first(|n| *n > 4), Some(5));
+        assert_eq!(deque.remove_first(|n| *n == 0), Some(0));
+        assert_eq!(deque.remove_first(|n| *n == 9), Some(9));
+        assert_eq!(deque.remove_first(|n| *n > 10), None);
+
+        assert_eq!(deque.len(), 7);
+        assert_eq!(to_vec(&deque), [1, 2, 3, 4, 6, 7, 8]);
+        assert_eq!(deque.peek_back(), Some(&8));
+    }
+
+    #[test]
+    fn linked_deque_dedup() {
+        let mut deque = [0, 0, 1, 1, 1, 2, 3, 3, 0, 0]
+            .into_iter()
+            .collect::<LinkedDeque<_>>();
+
+        deque.dedup();
+        assert_eq!(deque.len(), 5);
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3, 0]);
+        assert_eq!(
+            deque.iter().rev().copied().collect::<Vec<_>>(),
+            [0, 3, 2, 1, 0]
+        );
+
+        let mut deque = LinkedDeque::<i32>::new();
+        deque.dedup();
+        deque.push_back(0);
+        deque.push_back(0);
+        deque.dedup();
+        assert_eq!(deque.len(), 1);
+        assert_eq!(deque.head, deque.tail);
+    }
+
+    #[test]
+    fn linked_deque_dedup_by_key() {
+        let mut deque = (0..10).collect::<LinkedDeque<_>>();
+
+        deque.dedup_by_key(|n| *n / 3);
+        assert_eq!(deque.len(), 4);
+        assert_eq!(to_vec(&deque), [0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn linked_deque_dedup_handles() {
+        let mut deque = LinkedDeque::new();
+        let handles = [0, 0, 1].map(|n| deque.push_back_handle(n));
+
+        deque.dedup();
+        assert_eq!(deque.get(handles[0]), Some(&0));
+        assert_eq!(deque.get(handles[1]), None);
+        assert_eq!(deque.get(handles[2]), Some(&1));
+    }
+
+    #[test]
+    fn linked_deque_dedup_by_panic() {
+        let rc = std::rc::Rc::default();
+        let mut deque = [0, 0, 1, 1, 2, 2]
+            .into_iter()
+            .map(|n| (n, DropCounter::new(&rc, vec![n])))
+            .collect::<LinkedDeque<_>>();
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            deque.dedup_by(|a, b| {
+                assert_ne!(a.0, 2);
+                a.0 == b.0
+            })
+        }));
+        assert!(res.is_err());
+
+        assert_eq!(rc.get(), 2);
+        assert_eq!(deque.len(), 4);
+        assert_eq!(
+            deque.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            [0, 1, 2, 2]
+        );
+
+        drop(deque);
+        assert_eq!(rc.get(), 6);
+    }
+
+    #[test]
+    fn linked_deque_allocator() {
+        let alloc = TrackingAllocator::new();
+        let mut deque = LinkedDeque::new_in(&alloc);
+
+        deque.extend(0..10);
+        assert_eq!(alloc.allocations(), 10);
+        assert_eq!(alloc.live(), 10);
+
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(9));
+        deque.retain(|n| n % 3 != 0);
+        assert_eq!(alloc.live(), 6);
+
+        let handle = deque.push_front_handle(0);
+        assert_eq!(deque.remove(handle), Some(0));
+
+        let mut back = deque.split_off(2);
+        let clone = back.clone();
+        assert_eq!(alloc.live(), 10);
+
+        let mut cursor = deque.cursor_front_mut();
+        cursor.insert_after(3);
+        cursor.splice_before(back.split_off(0));
+        assert_eq!(to_vec(&deque), [4, 5, 7, 8, 1, 3, 2]);
+        assert_eq!(to_vec(&clone), [4, 5, 7, 8]);
+
+        deque.sort();
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), [1, 2, 3, 4, 5, 7, 8]);
+        assert_eq!(alloc.live(), 4);
+
+        drop(clone);
+        drop(back);
+        assert_eq!(alloc.live(), 0);
+    }
+
+    #[test]
+    fn linked_deque_allocator_append() {
+        let alloc = TrackingAllocator::new();
+        let alloc_other = TrackingAllocator::new();
+        let mut deque = LinkedDeque::new_in(&alloc);
+        let mut other = LinkedDeque::new_in(&alloc_other);
+
+        // Nodes from another allocator are moved over, never relinked
+        deque.extend([2, 3]);
+        other.extend([4, 5]);
+        deque.append(&mut other);
+        other.extend([0, 1]);
+        deque.prepend(&mut other);
+        assert_eq!(to_vec(&deque), [0, 1, 2, 3, 4, 5]);
+        assert_eq!(alloc.live(), 6);
+        assert_eq!(alloc_other.live(), 0);
+
+        let mut before = LinkedDeque::new_in(&alloc_other);
+        before.extend([6, 7]);
+        let mut after = LinkedDeque::new_in(&alloc_other);
+        after.extend([8, 9]);
+
+        let mut cursor = deque.cursor_front_mut();
+        cursor.move_next();
+        cursor.splice_before(before);
+        cursor.splice_after(after);
+        assert_eq!(to_vec(&deque), [0, 6, 7, 1, 8, 9, 2, 3, 4, 5]);
+        assert_eq!(alloc.live(), 10);
+        assert_eq!(alloc_other.live(), 0);
+
+        drop(deque);
+        assert_eq!(alloc.live(), 0);
+    }
 }
@@ -0,0 +1,53 @@
+//! Compares lists with atomic and non-atomic reference counts on the operations which update them
+//! the most: building a list, sharing it and dropping it.
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use dsa_linkedrefcount::{ArcKind, LinkedRefCount, PointerKind, RcKind};
+
+const LEN: usize = 10_000;
+
+fn prepend<K: PointerKind>() -> LinkedRefCount<usize, K> {
+    (0..LEN).fold(LinkedRefCount::new(), |list, n| list.prepend(n))
+}
+
+/// Takes the tail of every suffix of the list, which increments and decrements each count once.
+fn tails<K: PointerKind>(list: &LinkedRefCount<usize, K>) -> usize {
+    let mut list = list.clone();
+    let mut len = 0;
+    while !list.is_empty() {
+        list = list.tail();
+        len += 1;
+    }
+    len
+}
+
+fn bench_prepend(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prepend");
+    group.bench_function("rc", |b| b.iter(prepend::<RcKind>));
+    group.bench_function("arc", |b| b.iter(prepend::<ArcKind>));
+    group.finish();
+}
+
+fn bench_tails(c: &mut Criterion) {
+    let rc = prepend::<RcKind>();
+    let arc = prepend::<ArcKind>();
+
+    let mut group = c.benchmark_group("tails");
+    group.bench_function("rc", |b| b.iter(|| tails(std::hint::black_box(&rc))));
+    group.bench_function("arc", |b| b.iter(|| tails(std::hint::black_box(&arc))));
+    group.finish();
+}
+
+fn bench_drop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("drop");
+    group.bench_function("rc", |b| {
+        b.iter_batched(prepend::<RcKind>, drop, BatchSize::LargeInput)
+    });
+    group.bench_function("arc", |b| {
+        b.iter_batched(prepend::<ArcKind>, drop, BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_prepend, bench_tails, bench_drop);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+/// The kind of reference-counted pointer used for the nodes of a
+/// [`LinkedRefCount`](crate::LinkedRefCount), either [`RcKind`] or [`ArcKind`].
+///
+/// This trait is sealed: a bogus reference count would free nodes which are still in use, so it
+/// cannot be implemented outside of this crate.
+pub trait PointerKind: sealed::Sealed {}
+
+/// Non-atomic reference counts, like [`std::rc::Rc`].
+///
+/// Cloning and dropping lists is cheaper, but they cannot be sent to or shared with other
+/// threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct RcKind;
+
+/// Atomic reference counts, like [`std::sync::Arc`].
+///
+/// Lists can be sent to and shared with other threads as long as their elements can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ArcKind;
+
+impl PointerKind for RcKind {}
+impl PointerKind for ArcKind {}
+
+pub(crate) mod sealed {
+    /// Reference count operations, with the same memory orderings as [`std::sync::Arc`] where
+    /// they matter.
+    pub trait Sealed {
+        type Count;
+
+        /// Returns a count of 1.
+        fn new_count() -> Self::Count;
+
+        /// Returns the current count. This synchronizes with every previous decrement.
+        fn load(count: &Self::Count) -> usize;
+
+        /// Increments the count and returns its previous value.
+        fn increment(count: &Self::Count) -> usize;
+
+        /// Decrements the count and returns its previous value. Accesses made through the pointer
+        /// being dropped happen before the [`acquire`](Self::acquire) which follows the last
+        /// decrement.
+        fn decrement(count: &Self::Count) -> usize;
+
+        /// Synchronizes with every previous decrement, before the value is dropped.
+        fn acquire();
+
+        /// Sets the count to 0 if it is 1, returning whether it did. This synchronizes with every
+        /// previous decrement on success.
+        fn try_claim(count: &Self::Count) -> bool;
+    }
+
+    impl Sealed for super::RcKind {
+        type Count = std::cell::Cell<usize>;
+
+        fn new_count() -> Self::Count {
+            std::cell::Cell::new(1)
+        }
+
+        fn load(count: &Self::Count) -> usize {
+            count.get()
+        }
+
+        fn increment(count: &Self::Count) -> usize {
+            let previous = count.get();
+            count.set(previous + 1);
+            previous
+        }
+
+        fn decrement(count: &Self::Count) -> usize {
+            let previous = count.get();
+            count.set(previous - 1);
+            previous
+        }
+
+        fn acquire() {}
+
+        fn try_claim(count: &Self::Count) -> bool {
+            if count.get() == 1 {
+                count.set(0);
+                true
+            } else {
+                false
+            }
+        }
+    }
+
+    impl Sealed for super::ArcKind {
+        type Count = std::sync::atomic::AtomicUsize;
+
+        fn new_count() -> Self::Count {
+            std::sync::atomic::AtomicUsize::new(1)
+        }
+
+        fn load(count: &Self::Count) -> usize {
+            count.load(std::sync::atomic::Ordering::Acquire)
+        }
+
+        fn increment(count: &Self::Count) -> usize {
+            // Relaxed is enough since a new pointer can only be made from an existing one
+            count.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        }
+
+        fn decrement(count: &Self::Count) -> usize {
+            count.fetch_sub(1, std::sync::atomic::Ordering::Release)
+        }
+
+        fn acquire() {
+            std::sync::atomic::fence(std::sync::atomic::Ordering::Acquire);
+        }
+
+        fn try_claim(count: &Self::Count) -> bool {
+            count
+                .compare_exchange(
+                    1,
+                    0,
+                    std::sync::atomic::Ordering::Acquire,
+                    std::sync::atomic::Ordering::Relaxed,
+                )
+                .is_ok()
+        }
+    }
+}
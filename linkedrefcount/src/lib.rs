@@ -1,76 +1,133 @@
-use itertools::{
-    FoldWhile::{Continue, Done},
-    Itertools,
-};
+use allocator_api2::alloc::{Allocator, Global};
 
-struct LinkedRefCount<T> {
-    head: Link<T>,
+pub mod kind;
+pub mod queue;
+pub mod random_access;
+mod shared;
+
+pub use kind::{ArcKind, PointerKind, RcKind};
+pub use queue::{PersistentQueue, RealTimeQueue};
+pub use random_access::RandomAccessList;
+use shared::Shared;
+
+/// A persistent, immutable singly-linked list, also known as a cons list.
+///
+/// Lists are never modified in place: every operation returns a new list instead, which shares as
+/// much of its structure as it can with the lists it was made from. Nodes are reference-counted,
+/// so they are freed once the last list pointing to them is dropped. Each method documents which
+/// nodes it shares and which it has to copy.
+///
+/// The reference counts are either atomic or not depending on `K`, see [`PointerKind`]. The
+/// default [`ArcKind`] lets lists be sent across threads, while [`RcKind`] avoids paying for
+/// atomic operations when they never leave the one they were made on. [`ArcList`] and [`RcList`]
+/// are shorthands for either.
+///
+/// Nodes are allocated with `A`, which defaults to the global allocator. Use
+/// [`new_in`](Self::new_in) to allocate them somewhere else, such as a bump arena.
+pub struct LinkedRefCount<T, K: PointerKind = ArcKind, A: Allocator = Global> {
+    head: Link<T, K, A>,
     size: usize,
+    alloc: A,
 }
 
-type Link<T> = Option<std::sync::Arc<Node<T>>>;
+/// A [`LinkedRefCount`] with non-atomic reference counts, which cannot be sent across threads.
+///
+/// ```compile_fail
+/// let list = dsa_linkedrefcount::RcList::new().prepend(0);
+/// std::thread::spawn(move || list.len());
+/// ```
+pub type RcList<T, A = Global> = LinkedRefCount<T, RcKind, A>;
+
+/// A [`LinkedRefCount`] with atomic reference counts, which can be sent across threads.
+pub type ArcList<T, A = Global> = LinkedRefCount<T, ArcKind, A>;
 
-pub struct Node<T> {
+type Link<T, K, A> = Option<Shared<Node<T, K, A>, K, A>>;
+
+struct Node<T, K: PointerKind, A: Allocator> {
     elem: T,
-    next: Link<T>,
+    next: Link<T, K, A>,
 }
 
-impl<T: std::fmt::Debug> std::fmt::Debug for LinkedRefCount<T> {
+impl<T: std::fmt::Debug, K: PointerKind, A: Allocator> std::fmt::Debug for LinkedRefCount<T, K, A> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_list().entries(self.iter()).finish()
     }
 }
 
-impl<T: Clone> Clone for LinkedRefCount<T> {
+/// Cloning a list is O(1): the clone shares every node with the original.
+impl<T, K: PointerKind, A: Allocator + Clone> Clone for LinkedRefCount<T, K, A> {
     fn clone(&self) -> Self {
-        let mut iter = self.iter().cloned();
-        let head = iter.next().and_then(|elem| {
-            let mut head = Some(std::sync::Arc::new(Node { elem, next: None }));
-            let mut cursor = head.as_mut();
-
-            for elem in iter {
-                unsafe {
-                    // sadly `get_mut_unchecked` is a nightly-only api as of now
-                    // https://doc.rust-lang.org/std/sync/struct.Arc.html#method.get_mut_unchecked
-                    let inner =
-                        std::sync::Arc::get_mut(cursor.unwrap_unchecked()).unwrap_unchecked();
-                    inner.next = Some(std::sync::Arc::new(Node { elem, next: None }));
-                    cursor = inner.next.as_mut();
-                }
-            }
-
-            head
-        });
-
         Self {
-            head,
+            head: self.head.clone(),
             size: self.size,
+            alloc: self.alloc.clone(),
         }
     }
 }
 
-impl<T: PartialEq> PartialEq for LinkedRefCount<T> {
+/// Comparison stops as soon as both lists reach the same node, since the remaining suffix is then
+/// physically shared and has to be equal. Comparing two versions of a list which only differ in
+/// their first few elements is therefore cheap, no matter how long they are.
+///
+/// Shared elements are never compared with themselves, so a list holding a value which is not
+/// equal to itself, such as [`f64::NAN`], is still equal to its clones. [`PartialOrd`] takes the
+/// same shortcut to stay consistent with this.
+impl<T: PartialEq, K: PointerKind, A: Allocator> PartialEq for LinkedRefCount<T, K, A> {
     fn eq(&self, other: &Self) -> bool {
-        self.size == other.size
-            && self
-                .iter()
-                .zip(other.iter())
-                .fold_while(
-                    true,
-                    |_, (a, b)| {
-                        if a == b { Continue(true) } else { Done(false) }
-                    },
-                )
-                .into_inner()
+        if self.size != other.size {
+            return false;
+        }
+
+        let (mut lhs, mut rhs) = (&self.head, &other.head);
+        while let (Some(l), Some(r)) = (lhs, rhs) {
+            if Shared::ptr_eq(l, r) {
+                return true;
+            } else if l.elem != r.elem {
+                return false;
+            }
+
+            (lhs, rhs) = (&l.next, &r.next);
+        }
+
+        true
     }
 }
 
-impl<T: Eq> Eq for LinkedRefCount<T> {}
+impl<T: Eq, K: PointerKind, A: Allocator> Eq for LinkedRefCount<T, K, A> {}
 
-impl<T> Drop for LinkedRefCount<T> {
+/// Lists are compared lexicographically, from head to tail. Like [`PartialEq`], comparison stops
+/// as soon as both lists reach the same node.
+impl<T: PartialOrd, K: PointerKind, A: Allocator> PartialOrd for LinkedRefCount<T, K, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.compare_by(other, T::partial_cmp)
+    }
+}
+
+impl<T: Ord, K: PointerKind, A: Allocator> Ord for LinkedRefCount<T, K, A> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.compare_by(other, |lhs, rhs| Some(lhs.cmp(rhs)))
+            .expect("Elements with a total order can always be compared")
+    }
+}
+
+impl<T: std::hash::Hash, K: PointerKind, A: Allocator> std::hash::Hash for LinkedRefCount<T, K, A> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        // The length keeps lists of lists from colliding when their elements are split up
+        // differently, same as with slices
+        state.write_usize(self.size);
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<T, K: PointerKind, A: Allocator> Drop for LinkedRefCount<T, K, A> {
+    /// Nodes are dropped iteratively for as long as this list holds the last pointer to them. The
+    /// first node which is still shared with another list stops the walk, since everything after
+    /// it is kept alive by that list.
     fn drop(&mut self) {
         while let Some(head) = self.head.take() {
-            if let Ok(mut node) = std::sync::Arc::try_unwrap(head) {
+            if let Ok(mut node) = Shared::try_unwrap(head) {
                 self.head = node.next.take();
             } else {
                 break;
@@ -79,47 +136,336 @@ impl<T> Drop for LinkedRefCount<T> {
     }
 }
 
-impl<T> Default for LinkedRefCount<T> {
+impl<T, K: PointerKind> Default for LinkedRefCount<T, K> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T> LinkedRefCount<T> {
+impl<T, K: PointerKind> LinkedRefCount<T, K> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, K: PointerKind, A: Allocator + Clone> LinkedRefCount<T, K, A> {
+    /// Creates an empty list whose nodes are allocated with `alloc`.
+    pub fn new_in(alloc: A) -> Self {
         Self {
             head: None,
             size: 0,
+            alloc,
         }
     }
 
-    pub fn preprend(&self, elem: T) -> Self {
+    /// Builds a list out of `elems`, in order, followed by the `tail_size` nodes of `tail`. The
+    /// nodes of `tail` are shared rather than copied.
+    fn with_tail<I: IntoIterator<Item = T>>(
+        elems: I,
+        tail: Link<T, K, A>,
+        tail_size: usize,
+        alloc: A,
+    ) -> Self {
+        let mut list = Self::new_in(alloc);
+        let mut cursor = &mut list.head;
+
+        for elem in elems {
+            let node = cursor.insert(Shared::new_in(
+                Node { elem, next: None },
+                list.alloc.clone(),
+            ));
+            // The unwrap is safe since the node was just created, so this is the only pointer to it
+            cursor = &mut unsafe { Shared::get_mut(node).unwrap_unchecked() }.next;
+            list.size += 1;
+        }
+
+        *cursor = tail;
+        list.size += tail_size;
+        list
+    }
+
+    /// Returns a reference to the allocator used by the list.
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Returns a new list with `elem` in front of this one.
+    ///
+    /// This runs in O(1) time: only the new head is allocated, and every node of this list is
+    /// shared.
+    pub fn prepend(&self, elem: T) -> Self {
         Self {
-            head: Some(std::sync::Arc::new(Node {
-                elem,
-                next: self.head.clone(),
-            })),
+            head: Some(Shared::new_in(
+                Node {
+                    elem,
+                    next: self.head.clone(),
+                },
+                self.alloc.clone(),
+            )),
             size: self.size + 1,
+            alloc: self.alloc.clone(),
         }
     }
 
+    /// Returns the list without its first element, or an empty list if this one is empty.
+    ///
+    /// This runs in O(1) time and shares every remaining node.
     pub fn tail(&self) -> Self {
-        self.head
-            .as_ref()
-            .map(|head| Self {
-                head: head.next.clone(),
-                size: self.size - 1,
-            })
-            .unwrap_or(Self {
-                head: None,
-                size: 0,
-            })
+        self.skip(1)
+    }
+
+    /// Returns the list without its first `n` elements, or an empty list if this one has fewer
+    /// than `n` elements.
+    ///
+    /// This runs in O(n) time and shares every remaining node, nothing is allocated.
+    pub fn skip(&self, n: usize) -> Self {
+        let n = n.min(self.size);
+
+        Self {
+            head: link_at(&self.head, n).clone(),
+            size: self.size - n,
+            alloc: self.alloc.clone(),
+        }
+    }
+
+    /// Returns a list of the first `n` elements, or of all of them if the list has fewer than `n`
+    /// elements.
+    ///
+    /// When `n` is at least the length of the list this is just a [`clone`](Clone::clone), which
+    /// shares every node. Otherwise the last node would have to point somewhere else, so the
+    /// first `n` elements are cloned into new nodes and nothing is shared.
+    pub fn take(&self, n: usize) -> Self
+    where
+        T: Clone,
+    {
+        if n >= self.size {
+            self.clone()
+        } else {
+            Self::with_tail(self.iter().take(n).cloned(), None, 0, self.alloc.clone())
+        }
+    }
+
+    /// Returns a list of the elements in reverse order.
+    ///
+    /// Every element is cloned into a new node, since no node of this list can be reused with a
+    /// different successor.
+    pub fn reverse(&self) -> Self
+    where
+        T: Clone,
+    {
+        let mut head = None;
+        for elem in self.iter().cloned() {
+            head = Some(Shared::new_in(
+                Node { elem, next: head },
+                self.alloc.clone(),
+            ));
+        }
+
+        Self {
+            head,
+            size: self.size,
+            alloc: self.alloc.clone(),
+        }
+    }
+
+    /// Returns a list of the elements of this one followed by those of `other`.
+    ///
+    /// Every node of `other` is shared, while the elements of this list are cloned into new nodes
+    /// since the last one has to point to `other`. This runs in O(n) time, where n is the length
+    /// of this list, no matter how long `other` is.
+    pub fn append(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        Self::with_tail(
+            self.iter().cloned(),
+            other.head.clone(),
+            other.size,
+            self.alloc.clone(),
+        )
+    }
+
+    /// Returns a list of `f` applied to each element, in order.
+    ///
+    /// The elements change type, so nothing can be shared: every node is new.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> LinkedRefCount<U, K, A> {
+        LinkedRefCount::with_tail(self.iter().map(f), None, 0, self.alloc.clone())
+    }
+
+    /// Returns a list of the elements for which `f` returns `true`, in order. `f` is called once
+    /// on each element, from head to tail.
+    ///
+    /// The longest suffix in which every element is kept is shared with this list, so filtering
+    /// out an element only copies the kept elements in front of it. In particular, a filter which
+    /// keeps everything allocates nothing.
+    pub fn filter<F: FnMut(&T) -> bool>(&self, f: F) -> Self
+    where
+        T: Clone,
+    {
+        let keep = self.iter().map(f).collect::<Vec<_>>();
+        let shared = keep.iter().rev().take_while(|keep| **keep).count();
+        let copied = self.size - shared;
+
+        Self::with_tail(
+            self.iter()
+                .zip(keep)
+                .take(copied)
+                .filter(|(_, keep)| *keep)
+                .map(|(elem, _)| elem.clone()),
+            link_at(&self.head, copied).clone(),
+            shared,
+            self.alloc.clone(),
+        )
+    }
+
+    /// Returns the list with its `n`th element replaced by `elem`.
+    ///
+    /// Only the first `n + 1` nodes are copied, the rest are shared. Nodes which are not shared
+    /// with any other list are updated in place instead, so setting an element of a list nobody
+    /// else holds allocates nothing. Use [`clone`](Clone::clone) first to keep the original.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is out of bounds.
+    pub fn set(self, n: usize, elem: T) -> Self
+    where
+        T: Clone,
+    {
+        self.update(n, |old| *old = elem)
+    }
+
+    /// Returns the list with `f` applied to its `n`th element.
+    ///
+    /// This shares and copies the same nodes as [`set`](Self::set).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is out of bounds.
+    pub fn update<F: FnOnce(&mut T)>(mut self, n: usize, f: F) -> Self
+    where
+        T: Clone,
+    {
+        assert!(
+            n < self.size,
+            "Cannot update element {n}, list only has {} elements",
+            self.size
+        );
+
+        let alloc = self.alloc.clone();
+        // The unwrap is safe since we just checked that `n` is in bounds
+        let node = unsafe { self.unique_link_at(n).as_mut().unwrap_unchecked() };
+        f(&mut make_unique(node, &alloc).elem);
+        self
+    }
+
+    /// Returns the list with `elem` inserted at index `n`, so that it becomes the `n`th element.
+    ///
+    /// Only the first `n` nodes are copied, or updated in place if they are not shared, and the
+    /// nodes after `elem` are shared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the list.
+    pub fn insert(mut self, n: usize, elem: T) -> Self
+    where
+        T: Clone,
+    {
+        assert!(
+            n <= self.size,
+            "Cannot insert at index {n}, list only has {} elements",
+            self.size
+        );
+
+        let alloc = self.alloc.clone();
+        let link = self.unique_link_at(n);
+        let next = link.take();
+        *link = Some(Shared::new_in(Node { elem, next }, alloc));
+        self.size += 1;
+        self
+    }
+
+    /// Returns the list without its `n`th element.
+    ///
+    /// Only the first `n` nodes are copied, or updated in place if they are not shared, and the
+    /// nodes after the removed element are shared. The element itself is dropped unless another
+    /// list still holds it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is out of bounds.
+    pub fn remove(mut self, n: usize) -> Self
+    where
+        T: Clone,
+    {
+        assert!(
+            n < self.size,
+            "Cannot remove element {n}, list only has {} elements",
+            self.size
+        );
+
+        let link = self.unique_link_at(n);
+        // The unwrap is safe since we just checked that `n` is in bounds
+        let node = unsafe { link.take().unwrap_unchecked() };
+        *link = match Shared::try_unwrap(node) {
+            Ok(mut node) => node.next.take(),
+            Err(node) => node.next.clone(),
+        };
+        self.size -= 1;
+        self
+    }
+
+    /// Returns the link after the first `n` nodes, making sure that none of those nodes is shared
+    /// so they can be modified in place. The list must have at least `n` nodes.
+    ///
+    /// Shared nodes are replaced by copies. The copy then shares the next node with the original,
+    /// so every node after the first shared one ends up being copied as well.
+    fn unique_link_at(&mut self, n: usize) -> &mut Link<T, K, A>
+    where
+        T: Clone,
+    {
+        let mut link = &mut self.head;
+
+        for _ in 0..n {
+            // The unwrap is safe since the caller checked that the list is long enough
+            let node = unsafe { link.as_mut().unwrap_unchecked() };
+            link = &mut make_unique(node, &self.alloc).next;
+        }
+
+        link
+    }
+}
+
+/// Returns a mutable reference to the node, replacing it with a copy first if it is shared.
+fn make_unique<'a, T: Clone, K: PointerKind, A: Allocator + Clone>(
+    node: &'a mut Shared<Node<T, K, A>, K, A>,
+    alloc: &A,
+) -> &'a mut Node<T, K, A> {
+    if Shared::get_mut(node).is_none() {
+        *node = Shared::new_in(
+            Node {
+                elem: node.elem.clone(),
+                next: node.next.clone(),
+            },
+            alloc.clone(),
+        );
     }
 
+    // The unwrap is safe since the node is either unique or was just copied
+    unsafe { Shared::get_mut(node).unwrap_unchecked() }
+}
+
+impl<T, K: PointerKind, A: Allocator> LinkedRefCount<T, K, A> {
+    /// Returns a reference to the first element, or [`None`] if the list is empty.
     pub fn head(&self) -> Option<&T> {
         self.head.as_ref().map(|head| &head.elem)
     }
 
+    /// Returns a reference to the `n`th element, or [`None`] if `n` is out of bounds. This runs in
+    /// O(n) time.
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        self.iter().nth(n)
+    }
+
     pub fn len(&self) -> usize {
         self.size
     }
@@ -128,46 +474,199 @@ impl<T> LinkedRefCount<T> {
         self.size == 0
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &T> {
+    /// Returns `true` if both lists start at the same node, meaning they share all of their nodes.
+    /// Two empty lists are always pointer-equal.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.head, &other.head) {
+            (Some(lhs), Some(rhs)) => Shared::ptr_eq(lhs, rhs),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Compares both lists lexicographically with `compare`, stopping early once they reach the
+    /// same node.
+    fn compare_by<F>(&self, other: &Self, mut compare: F) -> Option<std::cmp::Ordering>
+    where
+        F: FnMut(&T, &T) -> Option<std::cmp::Ordering>,
+    {
+        let (mut lhs, mut rhs) = (&self.head, &other.head);
+        loop {
+            match (lhs, rhs) {
+                (Some(l), Some(r)) if Shared::ptr_eq(l, r) => {
+                    return Some(std::cmp::Ordering::Equal);
+                }
+                (Some(l), Some(r)) => match compare(&l.elem, &r.elem)? {
+                    std::cmp::Ordering::Equal => (lhs, rhs) = (&l.next, &r.next),
+                    ordering => return Some(ordering),
+                },
+                (l, r) => return Some(l.is_some().cmp(&r.is_some())),
+            }
+        }
+    }
+
+    /// Returns the length of the longest suffix which is physically shared by both lists, as in
+    /// made of the same nodes rather than just equal elements.
+    ///
+    /// This runs in O(n) time, where n is the length of the longest list.
+    pub fn shares_suffix_with(&self, other: &Self) -> usize {
+        let len = self.size.min(other.size);
+        let mut lhs = link_at(&self.head, self.size - len);
+        let mut rhs = link_at(&other.head, other.size - len);
+
+        // Nodes never change successor, so the first node both lists go through starts the shared
+        // suffix. Lining the lists up by length first means it is reached at the same time by both
+        let mut remaining = len;
+        while let (Some(l), Some(r)) = (lhs, rhs) {
+            if Shared::ptr_eq(l, r) {
+                return remaining;
+            }
+
+            (lhs, rhs) = (&l.next, &r.next);
+            remaining -= 1;
+        }
+
+        0
+    }
+
+    /// Returns the number of pointers to the `n`th node, or [`None`] if `n` is out of bounds.
+    ///
+    /// Each node is pointed to by its predecessor in every list which goes through it, and by
+    /// every list which starts at it. A count of 1 therefore means that no other list can reach
+    /// the node through the same path, though it may still be shared if one of the nodes before
+    /// it is.
+    pub fn strong_count_at(&self, n: usize) -> Option<usize> {
+        (n < self.size).then(|| {
+            // The unwrap is safe since we just checked that `n` is in bounds
+            Shared::strong_count(unsafe { link_at(&self.head, n).as_ref().unwrap_unchecked() })
+        })
+    }
+
+    /// Returns `true` if no node of this list is shared with another list.
+    ///
+    /// This runs in O(n) time in the worst case, but stops at the first shared node.
+    pub fn is_unique(&self) -> bool {
+        let mut link = &self.head;
+        while let Some(node) = link {
+            if Shared::strong_count(node) != 1 {
+                return false;
+            }
+            link = &node.next;
+        }
+
+        true
+    }
+
+    /// Turns the list into an iterator which moves its elements out, from head to tail, or hands
+    /// it back if any of its nodes is shared with another list.
+    ///
+    /// Unlike [`into_iter`](IntoIterator::into_iter), this never clones an element, so it works
+    /// for any `T`.
+    pub fn into_iter_unique(self) -> Result<IntoIterUnique<T, K, A>, Self> {
+        if self.is_unique() {
+            Ok(IntoIterUnique(self))
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Iterates over the elements of the list from head to tail.
+    pub fn iter(&self) -> Iter<'_, T, K, A> {
         Iter { head: &self.head }
     }
 }
 
-impl<T> FromIterator<T> for LinkedRefCount<T> {
+/// Returns the link `n` nodes down the list, which must have at least `n` nodes.
+fn link_at<T, K: PointerKind, A: Allocator>(mut link: &Link<T, K, A>, n: usize) -> &Link<T, K, A> {
+    for _ in 0..n {
+        // The unwrap is safe since the caller checked that the list is long enough
+        link = &unsafe { link.as_ref().unwrap_unchecked() }.next;
+    }
+    link
+}
+
+/// Builds a list whose head is the first element yielded by the iterator, so iterating over the
+/// list yields the elements back in the same order.
+impl<T, K: PointerKind> FromIterator<T> for LinkedRefCount<T, K> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut iter = iter.into_iter();
-        let (head, size) = iter
-            .next()
-            .map(|elem| {
-                let mut head = Some(std::sync::Arc::new(Node { elem, next: None }));
-                let mut cursor = head.as_mut();
-                let mut size = 1;
-
-                for elem in iter {
-                    unsafe {
-                        // sadly `get_mut_unchecked` is a nightly-only api as of now
-                        // https://doc.rust-lang.org/std/sync/struct.Arc.html#method.get_mut_unchecked
-                        let inner =
-                            std::sync::Arc::get_mut(cursor.unwrap_unchecked()).unwrap_unchecked();
-                        inner.next = Some(std::sync::Arc::new(Node { elem, next: None }));
-                        cursor = inner.next.as_mut();
-                    }
-                    size += 1;
-                }
+        Self::with_tail(iter, None, 0, Global)
+    }
+}
 
-                (head, size)
-            })
-            .unwrap_or((None, 0));
+impl<'a, T, K: PointerKind, A: Allocator> IntoIterator for &'a LinkedRefCount<T, K, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, K, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Moves elements out of the list for as long as its nodes are not shared with another list, see
+/// [`IntoIter`].
+impl<T: Clone, K: PointerKind, A: Allocator + Clone> IntoIterator for LinkedRefCount<T, K, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, K, A>;
 
-        Self { head, size }
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
     }
 }
 
-struct Iter<'a, T> {
-    head: &'a Link<T>,
+/// Iterator over the elements of a list, from head to tail.
+///
+/// Nodes which are only held by the list are consumed, moving their element out. Once the
+/// iterator reaches a node which is shared with another list, every remaining element is cloned
+/// instead since the other list still needs them. Use [`LinkedRefCount::into_iter_unique`] to
+/// make sure nothing is ever cloned.
+pub struct IntoIter<T, K: PointerKind = ArcKind, A: Allocator = Global>(LinkedRefCount<T, K, A>);
+
+impl<T: Clone, K: PointerKind, A: Allocator + Clone> Iterator for IntoIter<T, K, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let head = self.0.head.take()?;
+        self.0.size -= 1;
+
+        match Shared::try_unwrap(head) {
+            Ok(mut node) => {
+                self.0.head = node.next.take();
+                Some(node.elem)
+            }
+            Err(head) => {
+                self.0.head = head.next.clone();
+                Some(head.elem.clone())
+            }
+        }
+    }
+}
+
+/// Iterator which moves the elements out of a list none of whose nodes are shared, from head to
+/// tail. This is returned by [`LinkedRefCount::into_iter_unique`].
+pub struct IntoIterUnique<T, K: PointerKind = ArcKind, A: Allocator = Global>(
+    LinkedRefCount<T, K, A>,
+);
+
+impl<T, K: PointerKind, A: Allocator> Iterator for IntoIterUnique<T, K, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let head = self.0.head.take()?;
+        self.0.size -= 1;
+
+        // The unwrap is safe since `into_iter_unique` checked that no node was shared, and the
+        // iterator holds the only pointer to the list so no other can be made since
+        let mut node = unsafe { Shared::try_unwrap(head).unwrap_unchecked() };
+        self.0.head = node.next.take();
+        Some(node.elem)
+    }
+}
+
+pub struct Iter<'a, T, K: PointerKind = ArcKind, A: Allocator = Global> {
+    head: &'a Link<T, K, A>,
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, K: PointerKind, A: Allocator> Iterator for Iter<'a, T, K, A> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -180,7 +679,7 @@ impl<'a, T> Iterator for Iter<'a, T> {
 
 #[cfg(test)]
 mod test {
-    use dsa_util::DropCounter;
+    use dsa_util::{DropCounter, TrackingAllocator};
 
     use super::*;
 
@@ -190,17 +689,18 @@ mod test {
             LinkedRefCount::<()>::new(),
             LinkedRefCount {
                 head: None,
-                size: 0
+                size: 0,
+                alloc: Global
             }
         )
     }
 
     #[test]
     fn linked_ref_count_prepend() {
-        let node_1 = LinkedRefCount::new().preprend(0);
-        let node_2_a = node_1.preprend(1);
-        let node_2_b = node_1.preprend(2);
-        let node_3 = node_2_a.preprend(3);
+        let node_1 = ArcList::new().prepend(0);
+        let node_2_a = node_1.prepend(1);
+        let node_2_b = node_1.prepend(2);
+        let node_3 = node_2_a.prepend(3);
 
         assert_eq!(node_1.head.as_ref().unwrap().elem, 0);
         assert_eq!(node_2_a.head.as_ref().unwrap().elem, 1);
@@ -212,17 +712,17 @@ mod test {
         assert_eq!(node_2_b.size, 2);
         assert_eq!(node_3.size, 3);
 
-        assert!(std::sync::Arc::ptr_eq(
+        assert!(Shared::ptr_eq(
             node_2_a.head.as_ref().unwrap().next.as_ref().unwrap(),
             node_1.head.as_ref().unwrap()
         ));
 
-        assert!(std::sync::Arc::ptr_eq(
+        assert!(Shared::ptr_eq(
             node_2_b.head.as_ref().unwrap().next.as_ref().unwrap(),
             node_1.head.as_ref().unwrap()
         ));
 
-        assert!(std::sync::Arc::ptr_eq(
+        assert!(Shared::ptr_eq(
             node_3.head.as_ref().unwrap().next.as_ref().unwrap(),
             node_2_a.head.as_ref().unwrap()
         ));
@@ -230,16 +730,17 @@ mod test {
 
     #[test]
     fn linked_ref_count_tail() {
-        let node_1 = LinkedRefCount::new().preprend(0);
-        let node_2_a = node_1.preprend(1);
-        let node_2_b = node_1.preprend(2);
-        let node_3 = node_2_a.preprend(3);
+        let node_1 = ArcList::new().prepend(0);
+        let node_2_a = node_1.prepend(1);
+        let node_2_b = node_1.prepend(2);
+        let node_3 = node_2_a.prepend(3);
 
         assert_eq!(
             node_1.tail(),
             LinkedRefCount {
                 head: None,
-                size: 0
+                size: 0,
+                alloc: Global
             }
         );
 
@@ -251,17 +752,17 @@ mod test {
         assert_eq!(tail_1_b.size, 1);
         assert_eq!(tail_2_a.size, 2);
 
-        assert!(std::sync::Arc::ptr_eq(
+        assert!(Shared::ptr_eq(
             tail_1_a.head.as_ref().unwrap(),
             node_1.head.as_ref().unwrap()
         ));
 
-        assert!(std::sync::Arc::ptr_eq(
+        assert!(Shared::ptr_eq(
             tail_1_b.head.as_ref().unwrap(),
             node_1.head.as_ref().unwrap()
         ));
 
-        assert!(std::sync::Arc::ptr_eq(
+        assert!(Shared::ptr_eq(
             tail_2_a.head.as_ref().unwrap(),
             node_2_a.head.as_ref().unwrap()
         ));
@@ -269,10 +770,10 @@ mod test {
 
     #[test]
     fn linked_ref_count_head() {
-        let node_1 = LinkedRefCount::new().preprend(0);
-        let node_2_a = node_1.preprend(1);
-        let node_2_b = node_1.preprend(2);
-        let node_3 = node_2_a.preprend(3);
+        let node_1 = ArcList::new().prepend(0);
+        let node_2_a = node_1.prepend(1);
+        let node_2_b = node_1.prepend(2);
+        let node_3 = node_2_a.prepend(3);
 
         assert_eq!(node_1.head(), Some(0).as_ref());
         assert_eq!(node_2_a.head(), Some(1).as_ref());
@@ -282,11 +783,11 @@ mod test {
 
     #[test]
     fn linked_ref_count_iter() {
-        let mut node = LinkedRefCount::new();
+        let mut node = ArcList::new();
         assert_eq!(node.iter().next(), None);
 
         for n in (0..10).rev() {
-            node = node.preprend(n);
+            node = node.prepend(n);
         }
 
         let mut iter = node.iter();
@@ -298,9 +799,9 @@ mod test {
 
     #[test]
     fn linked_ref_count_debug() {
-        let mut node = LinkedRefCount::new();
+        let mut node = ArcList::new();
         for n in (0..10).rev() {
-            node = node.preprend(n);
+            node = node.prepend(n);
         }
 
         assert_eq!(&format!("{node:?}"), "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
@@ -308,9 +809,9 @@ mod test {
 
     #[test]
     fn linked_ref_count_clone() {
-        let mut node_1 = LinkedRefCount::new();
+        let mut node_1 = ArcList::new();
         for n in (0..10).rev() {
-            node_1 = node_1.preprend(n);
+            node_1 = node_1.prepend(n);
         }
         let node_2 = node_1.clone();
 
@@ -323,18 +824,556 @@ mod test {
             assert_eq!(iter_1.next(), Some(n).as_ref());
             assert_eq!(iter_2.next(), Some(n).as_ref());
         }
+
+        assert!(Shared::ptr_eq(
+            node_1.head.as_ref().unwrap(),
+            node_2.head.as_ref().unwrap()
+        ));
     }
 
     #[test]
     fn linked_ref_count_drop() {
-        let mut node = LinkedRefCount::new();
+        let mut node = ArcList::new();
         let counter = std::rc::Rc::default();
 
         for n in (0..10).rev() {
-            node = node.preprend(DropCounter::new(&counter, vec![n]));
+            node = node.prepend(DropCounter::new(&counter, vec![n]));
         }
 
         drop(node);
         assert_eq!(counter.get(), 10);
     }
+
+    #[test]
+    fn linked_ref_count_allocator() {
+        let alloc = TrackingAllocator::new();
+        let counter = std::rc::Rc::default();
+
+        let node_1 = ArcList::new_in(&alloc).prepend(DropCounter::new(&counter, vec![0]));
+        let node_2_a = node_1.prepend(DropCounter::new(&counter, vec![1]));
+        let node_2_b = node_1.prepend(DropCounter::new(&counter, vec![2]));
+        assert_eq!(alloc.allocations(), 3);
+
+        drop(node_1);
+        drop(node_2_a);
+        assert_eq!(alloc.live(), 2);
+        assert_eq!(counter.get(), 1);
+
+        let tail = node_2_b.tail();
+        drop(node_2_b);
+        assert_eq!(alloc.live(), 1);
+        assert_eq!(counter.get(), 2);
+
+        drop(tail);
+        assert_eq!(alloc.live(), 0);
+        assert_eq!(counter.get(), 3);
+    }
+
+    #[test]
+    fn linked_ref_count_nth() {
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+
+        for n in 0..10 {
+            assert_eq!(list.nth(n), Some(&n));
+        }
+        assert_eq!(list.nth(10), None);
+    }
+
+    #[test]
+    fn linked_ref_count_skip() {
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+
+        let skipped = list.skip(4);
+        assert_eq!(skipped, (4..10).collect());
+        assert_eq!(list.shares_suffix_with(&skipped), 6);
+
+        assert_eq!(list.skip(10), ArcList::new());
+        assert_eq!(list.skip(20), ArcList::new());
+        assert_eq!(LinkedRefCount::<i32>::new().tail(), ArcList::new());
+    }
+
+    #[test]
+    fn linked_ref_count_take() {
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+
+        let taken = list.take(4);
+        assert_eq!(taken, (0..4).collect());
+        assert_eq!(list.shares_suffix_with(&taken), 0);
+
+        let taken = list.take(20);
+        assert_eq!(taken, list);
+        assert!(list.ptr_eq(&taken));
+
+        assert_eq!(list.take(0), ArcList::new());
+    }
+
+    #[test]
+    fn linked_ref_count_reverse() {
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+
+        assert_eq!(list.reverse(), (0..10).rev().collect());
+        assert_eq!(list.reverse().reverse(), list);
+        assert_eq!(LinkedRefCount::<i32>::new().reverse(), ArcList::new());
+    }
+
+    #[test]
+    fn linked_ref_count_append() {
+        let lhs = (0..4).collect::<LinkedRefCount<_>>();
+        let rhs = (4..10).collect::<LinkedRefCount<_>>();
+
+        let appended = lhs.append(&rhs);
+        assert_eq!(appended, (0..10).collect());
+        assert_eq!(appended.len(), 10);
+        assert_eq!(appended.shares_suffix_with(&rhs), 6);
+
+        // The left-hand side is left untouched
+        assert_eq!(lhs, (0..4).collect());
+
+        assert_eq!(ArcList::new().append(&rhs), rhs);
+        assert_eq!(lhs.append(&ArcList::new()), lhs);
+    }
+
+    #[test]
+    fn linked_ref_count_map() {
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+
+        let mapped = list.map(|n| n.to_string());
+        assert_eq!(mapped, (0..10).map(|n| n.to_string()).collect());
+        assert_eq!(mapped.len(), 10);
+    }
+
+    #[test]
+    fn linked_ref_count_filter() {
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+
+        let filtered = list.filter(|n| n % 2 == 0 || *n > 4);
+        assert_eq!(filtered, [0, 2, 4, 5, 6, 7, 8, 9].into_iter().collect());
+        assert_eq!(filtered.len(), 8);
+        assert_eq!(list.shares_suffix_with(&filtered), 6);
+
+        let alloc = TrackingAllocator::new();
+        let list = (0..10).fold(ArcList::new_in(&alloc), |list, n| list.prepend(n));
+        let filtered = list.filter(|_| true);
+        assert_eq!(alloc.allocations(), 10);
+        assert_eq!(filtered, list);
+
+        let mut calls = 0;
+        assert!(
+            list.filter(|_| {
+                calls += 1;
+                false
+            })
+            .is_empty()
+        );
+        assert_eq!(calls, 10);
+    }
+
+    #[test]
+    fn linked_ref_count_into_iter() {
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+
+        let mut n = 0;
+        for elem in &list {
+            assert_eq!(*elem, n);
+            n += 1;
+        }
+        assert_eq!(n, 10);
+    }
+
+    #[test]
+    fn linked_ref_count_ord() {
+        let list = |elems: &[i32]| elems.iter().copied().collect::<LinkedRefCount<_>>();
+
+        assert!(list(&[0, 1, 2]) < list(&[0, 1, 3]));
+        assert!(list(&[0, 1]) < list(&[0, 1, 2]));
+        assert!(list(&[]) < list(&[0]));
+        assert!(list(&[1]) > list(&[0, 1, 2]));
+        assert_eq!(list(&[0, 1]).cmp(&list(&[0, 1])), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn linked_ref_count_hash() {
+        use std::hash::BuildHasher;
+
+        let state = std::hash::RandomState::new();
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+
+        assert_eq!(state.hash_one(&list), state.hash_one(list.clone()));
+        assert_eq!(
+            state.hash_one(&list),
+            state.hash_one((0..10).collect::<LinkedRefCount<_>>())
+        );
+        assert_ne!(state.hash_one(&list), state.hash_one(list.tail()));
+    }
+
+    #[test]
+    fn linked_ref_count_ptr_eq() {
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+
+        assert!(list.ptr_eq(&list.clone()));
+        assert!(list.tail().ptr_eq(&list.skip(1)));
+        assert!(!list.ptr_eq(&(0..10).collect()));
+        assert!(!list.ptr_eq(&list.tail()));
+        assert!(LinkedRefCount::<i32>::new().ptr_eq(&list.skip(10)));
+    }
+
+    #[test]
+    fn linked_ref_count_shares_suffix_with() {
+        let base = (0..10).collect::<LinkedRefCount<_>>();
+        let lhs = base.prepend(10).prepend(11);
+        let rhs = base.tail().prepend(12);
+
+        assert_eq!(lhs.shares_suffix_with(&rhs), 9);
+        assert_eq!(rhs.shares_suffix_with(&lhs), 9);
+        assert_eq!(lhs.shares_suffix_with(&lhs), 12);
+        assert_eq!(lhs.shares_suffix_with(&base), 10);
+        assert_eq!(lhs.shares_suffix_with(&(0..10).collect()), 0);
+        assert_eq!(lhs.shares_suffix_with(&ArcList::new()), 0);
+    }
+
+    #[test]
+    fn linked_ref_count_strong_count_at() {
+        let base = (0..3).collect::<LinkedRefCount<_>>();
+        let lhs = base.prepend(3);
+        let rhs = base.prepend(4);
+
+        // `base` and both new heads point to the first node of `base`
+        assert_eq!(lhs.strong_count_at(0), Some(1));
+        assert_eq!(lhs.strong_count_at(1), Some(3));
+        assert_eq!(lhs.strong_count_at(2), Some(1));
+        assert_eq!(lhs.strong_count_at(3), Some(1));
+        assert_eq!(lhs.strong_count_at(4), None);
+
+        drop(base);
+        assert_eq!(rhs.strong_count_at(1), Some(2));
+        drop(lhs);
+        assert_eq!(rhs.strong_count_at(1), Some(1));
+    }
+
+    #[test]
+    fn linked_ref_count_is_unique() {
+        let list = (0..10).collect::<LinkedRefCount<_>>();
+        assert!(list.is_unique());
+        assert!(LinkedRefCount::<i32>::new().is_unique());
+
+        let tail = list.skip(5);
+        assert!(!list.is_unique());
+        assert!(!tail.is_unique());
+
+        drop(tail);
+        assert!(list.is_unique());
+
+        let prepended = list.prepend(10);
+        assert!(!prepended.is_unique());
+    }
+
+    #[test]
+    fn linked_ref_count_eq_shared_suffix() {
+        struct Counted<'a>(i32, &'a std::cell::Cell<usize>);
+
+        impl PartialEq for Counted<'_> {
+            fn eq(&self, other: &Self) -> bool {
+                self.1.set(self.1.get() + 1);
+                self.0 == other.0
+            }
+        }
+
+        let calls = std::cell::Cell::new(0);
+        let base = (0..1000)
+            .map(|n| Counted(n, &calls))
+            .collect::<LinkedRefCount<_>>();
+        let lhs = base.prepend(Counted(0, &calls)).prepend(Counted(1, &calls));
+        let rhs = base.prepend(Counted(0, &calls)).prepend(Counted(1, &calls));
+
+        assert!(lhs == rhs);
+        assert_eq!(calls.get(), 2);
+
+        assert!(base == base.clone());
+        assert_eq!(calls.get(), 2);
+
+        let other = base.prepend(Counted(2, &calls)).prepend(Counted(1, &calls));
+        assert!(lhs != other);
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[test]
+    fn linked_ref_count_cmp_nan() {
+        let list = [0.0, f64::NAN, 1.0]
+            .into_iter()
+            .collect::<LinkedRefCount<_>>();
+        let clone = list.clone();
+
+        // Shared NaNs are never compared, so equality and ordering agree
+        assert!(list == clone);
+        assert_eq!(list.partial_cmp(&clone), Some(std::cmp::Ordering::Equal));
+
+        // Separate NaNs are compared, and are neither equal nor ordered
+        let other = [0.0, f64::NAN, 1.0]
+            .into_iter()
+            .collect::<LinkedRefCount<_>>();
+        assert!(list != other);
+        assert_eq!(list.partial_cmp(&other), None);
+
+        // Differences before the shared suffix still count
+        let lhs = clone.prepend(0.0);
+        let rhs = clone.prepend(1.0);
+        assert!(lhs < rhs);
+        assert_eq!(
+            lhs.partial_cmp(&clone.prepend(0.0)),
+            Some(std::cmp::Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn linked_ref_count_cmp_shared_suffix() {
+        let base = (0..1000).collect::<LinkedRefCount<_>>();
+
+        assert_eq!(base.cmp(&base.clone()), std::cmp::Ordering::Equal);
+        assert_eq!(
+            base.prepend(0).cmp(&base.prepend(1)),
+            std::cmp::Ordering::Less
+        );
+        assert_eq!(base.cmp(&base.tail()), std::cmp::Ordering::Less);
+        assert_eq!(base.tail().cmp(&base), std::cmp::Ordering::Greater);
+    }
+
+    #[test]
+    fn linked_ref_count_rc() {
+        let counter = std::rc::Rc::default();
+        let base = (0..3)
+            .map(|n| DropCounter::new(&counter, vec![n]))
+            .collect::<RcList<_>>();
+        let lhs = base.prepend(DropCounter::new(&counter, vec![3]));
+        let rhs = base.prepend(DropCounter::new(&counter, vec![4]));
+
+        assert_eq!(lhs.strong_count_at(1), Some(3));
+        assert_eq!(lhs.shares_suffix_with(&rhs), 3);
+
+        drop(base);
+        drop(lhs);
+        assert_eq!(counter.get(), 1);
+        assert_eq!(rhs.strong_count_at(1), Some(1));
+        assert!(rhs.is_unique());
+
+        let tail = rhs.tail();
+        drop(rhs);
+        assert_eq!(counter.get(), 2);
+        drop(tail);
+        assert_eq!(counter.get(), 5);
+    }
+
+    #[test]
+    fn linked_ref_count_arc_threads() {
+        let base = (0..100).collect::<ArcList<_>>();
+
+        let lists = (0..4)
+            .map(|n| {
+                let base = base.clone();
+                std::thread::spawn(move || (0..100).fold(base, |list, _| list.prepend(n)))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        for (n, list) in lists.iter().enumerate() {
+            assert_eq!(list.len(), 200);
+            assert_eq!(list.shares_suffix_with(&base), 100);
+            assert!(list.iter().take(100).all(|elem| *elem == n as i32));
+        }
+
+        drop(lists);
+        assert!(base.is_unique());
+    }
+
+    #[test]
+    fn linked_ref_count_set() {
+        let list = (0..10).collect::<ArcList<_>>();
+
+        let set = list.clone().set(3, 30);
+        assert_eq!(set, [0, 1, 2, 30, 4, 5, 6, 7, 8, 9].into_iter().collect());
+        assert_eq!(list, (0..10).collect());
+        assert_eq!(set.shares_suffix_with(&list), 6);
+
+        let set = set.set(0, 10).set(9, 90);
+        assert_eq!(set, [10, 1, 2, 30, 4, 5, 6, 7, 8, 90].into_iter().collect());
+        assert_eq!(set.shares_suffix_with(&list), 0);
+    }
+
+    #[test]
+    fn linked_ref_count_set_in_place() {
+        let alloc = TrackingAllocator::new();
+        let list = (0..10)
+            .rev()
+            .fold(ArcList::new_in(&alloc), |list, n| list.prepend(n));
+        assert_eq!(alloc.allocations(), 10);
+
+        // Nobody else holds the list, so nothing is copied
+        let list = list.set(5, 50).update(9, |n| *n += 1);
+        assert_eq!(alloc.allocations(), 10);
+
+        // Only the shared nodes up to the element are copied
+        let tail = list.skip(7);
+        let list = list.set(3, 30);
+        assert_eq!(alloc.allocations(), 10);
+        let list = list.set(8, 80);
+        assert_eq!(alloc.allocations(), 12);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 2, 30, 4, 50, 6, 7, 80, 10]
+        );
+        assert_eq!(tail.iter().copied().collect::<Vec<_>>(), [7, 8, 10]);
+
+        drop(list);
+        drop(tail);
+        assert_eq!(alloc.live(), 0);
+    }
+
+    #[test]
+    fn linked_ref_count_update() {
+        let list = (0..10).collect::<ArcList<_>>();
+
+        let updated = list.clone().update(4, |n| *n *= 10);
+        assert_eq!(
+            updated,
+            [0, 1, 2, 3, 40, 5, 6, 7, 8, 9].into_iter().collect()
+        );
+        assert_eq!(updated.shares_suffix_with(&list), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot update element 10, list only has 10 elements")]
+    fn linked_ref_count_set_out_of_bounds() {
+        (0..10).collect::<ArcList<_>>().set(10, 0);
+    }
+
+    #[test]
+    fn linked_ref_count_insert() {
+        let list = (0..10).collect::<ArcList<_>>();
+
+        let inserted = list.clone().insert(4, 40);
+        assert_eq!(
+            inserted,
+            [0, 1, 2, 3, 40, 4, 5, 6, 7, 8, 9].into_iter().collect()
+        );
+        assert_eq!(inserted.len(), 11);
+        assert_eq!(inserted.shares_suffix_with(&list), 6);
+
+        let inserted = inserted.insert(0, -1).insert(12, 10);
+        assert_eq!(inserted.len(), 13);
+        assert_eq!(inserted.head(), Some(&-1));
+        assert_eq!(inserted.nth(12), Some(&10));
+        assert_eq!(list, (0..10).collect());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot insert at index 11, list only has 10 elements")]
+    fn linked_ref_count_insert_out_of_bounds() {
+        (0..10).collect::<ArcList<_>>().insert(11, 0);
+    }
+
+    #[test]
+    fn linked_ref_count_remove() {
+        let list = (0..10).collect::<ArcList<_>>();
+
+        let removed = list.clone().remove(4);
+        assert_eq!(removed, [0, 1, 2, 3, 5, 6, 7, 8, 9].into_iter().collect());
+        assert_eq!(removed.len(), 9);
+        assert_eq!(removed.shares_suffix_with(&list), 5);
+
+        let removed = removed.remove(0).remove(7);
+        assert_eq!(removed, [1, 2, 3, 5, 6, 7, 8].into_iter().collect());
+        assert_eq!(list, (0..10).collect());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot remove element 0, list only has 0 elements")]
+    fn linked_ref_count_remove_out_of_bounds() {
+        ArcList::<i32>::new().remove(0);
+    }
+
+    #[test]
+    fn linked_ref_count_remove_drop() {
+        let counter = std::rc::Rc::default();
+        let list = (0..10)
+            .map(|n| std::rc::Rc::new(DropCounter::new(&counter, vec![n])))
+            .collect::<RcList<_>>();
+
+        // The removed element is still held by the tail
+        let tail = list.skip(5);
+        let list = list.remove(6);
+        assert_eq!(counter.get(), 0);
+        assert_eq!(list.len(), 9);
+
+        drop(tail);
+        assert_eq!(counter.get(), 1);
+
+        // Nobody else holds it anymore
+        let list = list.remove(2);
+        assert_eq!(counter.get(), 2);
+
+        drop(list);
+        assert_eq!(counter.get(), 10);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Cloned<'a>(i32, &'a std::cell::Cell<usize>);
+
+    impl Clone for Cloned<'_> {
+        fn clone(&self) -> Self {
+            self.1.set(self.1.get() + 1);
+            Self(self.0, self.1)
+        }
+    }
+
+    #[test]
+    fn linked_ref_count_into_iter_owned() {
+        let clones = std::cell::Cell::new(0);
+        let list = (0..10).map(|n| Cloned(n, &clones)).collect::<ArcList<_>>();
+
+        let elems = list.into_iter().map(|elem| elem.0).collect::<Vec<_>>();
+        assert_eq!(elems, (0..10).collect::<Vec<_>>());
+        assert_eq!(clones.get(), 0);
+    }
+
+    #[test]
+    fn linked_ref_count_into_iter_shared() {
+        let clones = std::cell::Cell::new(0);
+        let list = (0..10).map(|n| Cloned(n, &clones)).collect::<ArcList<_>>();
+
+        // Only the shared suffix is cloned
+        let tail = list.skip(6);
+        let elems = list.into_iter().map(|elem| elem.0).collect::<Vec<_>>();
+        assert_eq!(elems, (0..10).collect::<Vec<_>>());
+        assert_eq!(clones.get(), 4);
+
+        assert_eq!(tail.len(), 4);
+        assert!(tail.is_unique());
+    }
+
+    #[test]
+    fn linked_ref_count_into_iter_unique() {
+        let counter = std::rc::Rc::default();
+        let list = (0..10)
+            .map(|n| DropCounter::new(&counter, n))
+            .collect::<RcList<_>>();
+
+        let tail = list.skip(5);
+        let Err(list) = list.into_iter_unique() else {
+            panic!("The list shares its tail");
+        };
+
+        drop(tail);
+        let Ok(mut iter) = list.into_iter_unique() else {
+            panic!("The list is not shared anymore");
+        };
+
+        for _ in 0..4 {
+            drop(iter.next());
+        }
+        assert_eq!(counter.get(), 4);
+
+        drop(iter);
+        assert_eq!(counter.get(), 10);
+    }
 }
@@ -0,0 +1,701 @@
+use allocator_api2::alloc::Global;
+
+use crate::{ArcKind, LinkedRefCount, PointerKind, Shared};
+
+/// A persistent first-in first-out queue, after Okasaki's banker's queue.
+///
+/// Elements are pushed onto a rear [`LinkedRefCount`] and popped from a front one. Whenever the
+/// rear grows longer than the front, it is rotated onto the end of the front. That rotation is
+/// lazy: each node of the new front is only built the first time it is reached, and is then shared
+/// by every version of the queue which reaches it. This is what keeps
+/// [`push_back`](Self::push_back) and [`pop_front`](Self::pop_front) amortized O(1) even when old
+/// versions are pushed to or popped from over and over again, which would otherwise trigger the
+/// same O(n) rotation each time.
+///
+/// Every operation returns a new version of the queue and leaves the old one valid. Cloning a
+/// version is O(1). Elements are cloned as they are rotated to the front, so expensive elements
+/// are best wrapped in an [`Rc`](std::rc::Rc) or [`Arc`](std::sync::Arc).
+///
+/// The reference counts are either atomic or not depending on `K`, same as for
+/// [`LinkedRefCount`]. With [`RcKind`](crate::RcKind), the queue cannot be sent across threads.
+///
+/// See [`RealTimeQueue`] for a variant with worst-case O(1) operations.
+pub struct PersistentQueue<T, K: PointerKind = ArcKind> {
+    front: Front<T, K>,
+    front_len: usize,
+    rear: LinkedRefCount<T, K>,
+}
+
+/// A persistent first-in first-out queue with worst-case O(1) operations, after Okasaki's
+/// real-time queue.
+///
+/// This works just like [`PersistentQueue`], except that rotations are never left pending for
+/// long: every operation also builds one node of the front, following a schedule, so that no
+/// operation ever has to build more than that. This makes single operations a bit slower on
+/// average, in exchange for none of them ever stalling.
+pub struct RealTimeQueue<T, K: PointerKind = ArcKind> {
+    front: Front<T, K>,
+    rear: LinkedRefCount<T, K>,
+    /// Suffix of the front whose nodes have not been built yet. It is as long as the front is
+    /// longer than the rear, so that it is fully built by the time the next rotation starts.
+    schedule: Front<T, K>,
+    len: usize,
+}
+
+/// A list whose first nodes may not have been built yet. Nodes are built one at a time by
+/// rotations, and always end up in a fully built [`LinkedRefCount`].
+enum Front<T, K: PointerKind> {
+    List(LinkedRefCount<T, K>),
+    Lazy(Shared<Suspension<T, K>, K>),
+}
+
+/// A node of a [`Front`] which is built the first time it is needed, and memoized for every
+/// version of the queue which shares it.
+struct Suspension<T, K: PointerKind> {
+    node: std::sync::OnceLock<(T, Front<T, K>)>,
+    /// Kept until the node has been built. This is only accessed from the initializer passed to
+    /// `node`, which never runs concurrently with itself and never runs again once it succeeds,
+    /// or through a mutable reference.
+    rotation: std::cell::UnsafeCell<Option<Rotation<T, K>>>,
+}
+
+// SAFETY: the rotation is only accessed by one thread at a time, see above, so sharing a
+// suspension is no different from sending its rotation to the thread which builds the node
+unsafe impl<T: Send + Sync, K: PointerKind> Sync for Suspension<T, K> where K::Count: Sync {}
+
+/// A pending `front ++ reverse(rear) ++ acc`, where `rear` is one element longer than `front`.
+struct Rotation<T, K: PointerKind> {
+    front: Front<T, K>,
+    rear: LinkedRefCount<T, K>,
+    acc: LinkedRefCount<T, K>,
+}
+
+impl<T, K: PointerKind> Clone for Front<T, K> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::List(list) => Self::List(list.clone()),
+            Self::Lazy(suspension) => Self::Lazy(suspension.clone()),
+        }
+    }
+}
+
+impl<T: Clone, K: PointerKind> Front<T, K> {
+    /// Starts a lazy rotation of `rear` onto the end of `front`.
+    fn rotate(front: Self, rear: LinkedRefCount<T, K>, acc: LinkedRefCount<T, K>) -> Self {
+        debug_assert!(!rear.is_empty(), "A rotation always has a rear to reverse");
+
+        let suspension = Suspension {
+            node: std::sync::OnceLock::new(),
+            rotation: std::cell::UnsafeCell::new(Some(Rotation { front, rear, acc })),
+        };
+        Self::Lazy(Shared::new_in(suspension, Global))
+    }
+
+    fn first(&self) -> Option<&T> {
+        match self {
+            Self::List(list) => list.head(),
+            Self::Lazy(suspension) => Some(&suspension.force().0),
+        }
+    }
+
+    /// Returns the first element and the rest of the list, building the first node if needed.
+    fn split_first(&self) -> Option<(&T, Self)> {
+        match self {
+            Self::List(list) => list.head().map(|head| (head, Self::List(list.tail()))),
+            Self::Lazy(suspension) => {
+                let (head, rest) = suspension.force();
+                Some((head, rest.clone()))
+            }
+        }
+    }
+}
+
+impl<T: Clone, K: PointerKind> Suspension<T, K> {
+    fn force(&self) -> &(T, Front<T, K>) {
+        self.node.get_or_init(|| {
+            // SAFETY: `get_or_init` runs at most one initializer at a time, so nothing else is
+            // accessing the rotation
+            let rotation = unsafe { &*self.rotation.get() };
+            let Rotation { front, rear, acc } = rotation
+                .as_ref()
+                .expect("The rotation is only taken out once the node has been built");
+            let last = rear
+                .head()
+                .expect("The rear of a rotation is always one element longer than its front");
+
+            let node = match front.split_first() {
+                Some((head, front)) => (
+                    head.clone(),
+                    Front::rotate(front, rear.tail(), acc.prepend(last.clone())),
+                ),
+                None => (last.clone(), Front::List(acc.clone())),
+            };
+
+            // SAFETY: same as above, and the shared reference is no longer used. The rotation is
+            // only dropped once building the node has succeeded, so that a panicking clone leaves
+            // it in place for the next attempt
+            unsafe { *self.rotation.get() = None };
+            node
+        })
+    }
+}
+
+impl<T, K: PointerKind> Suspension<T, K> {
+    /// Takes the next suspension out of this one, if it is the only pointer to it.
+    fn take_next(&mut self) -> Option<Shared<Suspension<T, K>, K>> {
+        let front = match self.node.take() {
+            Some((_, front)) => front,
+            None => self.rotation.get_mut().take()?.front,
+        };
+
+        match front {
+            Front::Lazy(suspension) => Some(suspension),
+            Front::List(_) => None,
+        }
+    }
+}
+
+impl<T, K: PointerKind> Drop for Suspension<T, K> {
+    /// Suspensions are dropped iteratively for as long as this one holds the last pointer to
+    /// them, same as the nodes of a [`LinkedRefCount`].
+    fn drop(&mut self) {
+        let mut next = self.take_next();
+        while let Some(suspension) = next {
+            next = Shared::try_unwrap(suspension)
+                .ok()
+                .and_then(|mut suspension| suspension.take_next());
+        }
+    }
+}
+
+impl<T: std::fmt::Debug + Clone, K: PointerKind> std::fmt::Debug for PersistentQueue<T, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, K: PointerKind> Clone for PersistentQueue<T, K> {
+    fn clone(&self) -> Self {
+        Self {
+            front: self.front.clone(),
+            front_len: self.front_len,
+            rear: self.rear.clone(),
+        }
+    }
+}
+
+impl<T: PartialEq + Clone, K: PointerKind> PartialEq for PersistentQueue<T, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq + Clone, K: PointerKind> Eq for PersistentQueue<T, K> {}
+
+impl<T, K: PointerKind> Default for PersistentQueue<T, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pushes each element in turn, so the first element yielded by the iterator ends up at the front
+/// of the queue.
+impl<T: Clone, K: PointerKind> FromIterator<T> for PersistentQueue<T, K> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::new(), |queue, elem| queue.push_back(elem))
+    }
+}
+
+impl<T, K: PointerKind> PersistentQueue<T, K> {
+    pub fn new() -> Self {
+        Self {
+            front: Front::List(LinkedRefCount::new()),
+            front_len: 0,
+            rear: LinkedRefCount::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front_len + self.rear.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Clone, K: PointerKind> PersistentQueue<T, K> {
+    /// Restores the invariant that the rear is never longer than the front, which guarantees that
+    /// the front is only empty if the whole queue is.
+    fn check(front: Front<T, K>, front_len: usize, rear: LinkedRefCount<T, K>) -> Self {
+        if rear.len() <= front_len {
+            Self {
+                front,
+                front_len,
+                rear,
+            }
+        } else {
+            Self {
+                front_len: front_len + rear.len(),
+                front: Front::rotate(front, rear, LinkedRefCount::new()),
+                rear: LinkedRefCount::new(),
+            }
+        }
+    }
+
+    /// Returns a new version of the queue with `elem` at the back. This runs in amortized O(1)
+    /// time.
+    pub fn push_back(&self, elem: T) -> Self {
+        Self::check(self.front.clone(), self.front_len, self.rear.prepend(elem))
+    }
+
+    /// Returns the element at the front of the queue along with a new version of the queue
+    /// without it, or [`None`] if the queue is empty. This runs in amortized O(1) time.
+    pub fn pop_front(&self) -> Option<(&T, Self)> {
+        let (head, front) = self.front.split_first()?;
+        Some((
+            head,
+            Self::check(front, self.front_len - 1, self.rear.clone()),
+        ))
+    }
+
+    /// Returns a reference to the element at the front of the queue, or [`None`] if the queue is
+    /// empty. This runs in amortized O(1) time.
+    pub fn peek_front(&self) -> Option<&T> {
+        self.front.first()
+    }
+
+    /// Iterates over the elements of the queue from front to back.
+    ///
+    /// This builds any pending node of the front along the way. The rear is stored back to front,
+    /// so once the front is exhausted the iterator collects references to every element of the
+    /// rear in order to reverse them, which takes O(m) time and space for a rear of m elements.
+    /// Since the rear is never longer than the front, this is still O(1) amortized per element.
+    /// Nothing is collected if the iterator is dropped before reaching the rear.
+    pub fn iter(&self) -> Iter<'_, T, K> {
+        Iter::new(&self.front, &self.rear)
+    }
+}
+
+impl<T: std::fmt::Debug + Clone, K: PointerKind> std::fmt::Debug for RealTimeQueue<T, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, K: PointerKind> Clone for RealTimeQueue<T, K> {
+    fn clone(&self) -> Self {
+        Self {
+            front: self.front.clone(),
+            rear: self.rear.clone(),
+            schedule: self.schedule.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T: PartialEq + Clone, K: PointerKind> PartialEq for RealTimeQueue<T, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq + Clone, K: PointerKind> Eq for RealTimeQueue<T, K> {}
+
+impl<T, K: PointerKind> Default for RealTimeQueue<T, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pushes each element in turn, so the first element yielded by the iterator ends up at the front
+/// of the queue.
+impl<T: Clone, K: PointerKind> FromIterator<T> for RealTimeQueue<T, K> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::new(), |queue, elem| queue.push_back(elem))
+    }
+}
+
+impl<T, K: PointerKind> RealTimeQueue<T, K> {
+    pub fn new() -> Self {
+        Self {
+            front: Front::List(LinkedRefCount::new()),
+            rear: LinkedRefCount::new(),
+            schedule: Front::List(LinkedRefCount::new()),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T: Clone, K: PointerKind> RealTimeQueue<T, K> {
+    /// Builds the next node on the schedule, or starts a new rotation once every node has been
+    /// built, which happens right as the rear becomes one element longer than the front.
+    fn exec(
+        front: Front<T, K>,
+        rear: LinkedRefCount<T, K>,
+        schedule: Front<T, K>,
+        len: usize,
+    ) -> Self {
+        match schedule.split_first() {
+            Some((_, schedule)) => Self {
+                front,
+                rear,
+                schedule,
+                len,
+            },
+            None => {
+                let front = Front::rotate(front, rear, LinkedRefCount::new());
+                Self {
+                    schedule: front.clone(),
+                    front,
+                    rear: LinkedRefCount::new(),
+                    len,
+                }
+            }
+        }
+    }
+
+    /// Returns a new version of the queue with `elem` at the back. This runs in worst-case O(1)
+    /// time.
+    pub fn push_back(&self, elem: T) -> Self {
+        Self::exec(
+            self.front.clone(),
+            self.rear.prepend(elem),
+            self.schedule.clone(),
+            self.len + 1,
+        )
+    }
+
+    /// Returns the element at the front of the queue along with a new version of the queue
+    /// without it, or [`None`] if the queue is empty. This runs in worst-case O(1) time.
+    pub fn pop_front(&self) -> Option<(&T, Self)> {
+        let (head, front) = self.front.split_first()?;
+        Some((
+            head,
+            Self::exec(
+                front,
+                self.rear.clone(),
+                self.schedule.clone(),
+                self.len - 1,
+            ),
+        ))
+    }
+
+    /// Returns a reference to the element at the front of the queue, or [`None`] if the queue is
+    /// empty. This runs in worst-case O(1) time.
+    pub fn peek_front(&self) -> Option<&T> {
+        self.front.first()
+    }
+
+    /// Iterates over the elements of the queue from front to back, see
+    /// [`PersistentQueue::iter`] for the cost of reaching the rear.
+    pub fn iter(&self) -> Iter<'_, T, K> {
+        Iter::new(&self.front, &self.rear)
+    }
+}
+
+/// Iterator over the elements of a [`PersistentQueue`] or [`RealTimeQueue`], from front to back.
+pub struct Iter<'a, T, K: PointerKind = ArcKind> {
+    /// Pending part of the front, until the iterator reaches its fully built suffix.
+    front: Option<&'a Front<T, K>>,
+    list: Option<crate::Iter<'a, T, K>>,
+    rear: &'a LinkedRefCount<T, K>,
+    /// References to the elements of the rear, collected once the front is exhausted and popped
+    /// from the end.
+    reversed: Option<Vec<&'a T>>,
+}
+
+impl<'a, T, K: PointerKind> Iter<'a, T, K> {
+    fn new(front: &'a Front<T, K>, rear: &'a LinkedRefCount<T, K>) -> Self {
+        Self {
+            front: Some(front),
+            list: None,
+            rear,
+            reversed: None,
+        }
+    }
+}
+
+impl<'a, T: Clone, K: PointerKind> Iterator for Iter<'a, T, K> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.front.take() {
+            Some(Front::Lazy(suspension)) => {
+                let (head, rest) = suspension.force();
+                self.front = Some(rest);
+                return Some(head);
+            }
+            Some(Front::List(list)) => self.list = Some(list.iter()),
+            None => (),
+        }
+
+        self.list.as_mut().and_then(Iterator::next).or_else(|| {
+            self.reversed
+                .get_or_insert_with(|| self.rear.iter().collect())
+                .pop()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dsa_util::DropCounter;
+
+    use super::*;
+
+    #[test]
+    fn persistent_queue_push_pop() {
+        let mut queue = PersistentQueue::<_>::new();
+        assert_eq!(queue.pop_front(), None);
+        assert_eq!(queue.peek_front(), None);
+        assert!(queue.is_empty());
+
+        for n in 0..10 {
+            queue = queue.push_back(n);
+            assert_eq!(queue.peek_front(), Some(&0));
+        }
+        assert_eq!(queue.len(), 10);
+
+        for n in 0..10 {
+            let (elem, next) = queue.pop_front().unwrap();
+            assert_eq!(*elem, n);
+            queue = next;
+        }
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop_front(), None);
+    }
+
+    #[test]
+    fn persistent_queue_interleaved() {
+        let mut queue = PersistentQueue::<_>::new();
+        let mut expected = std::collections::VecDeque::new();
+
+        for n in 0..1000 {
+            queue = queue.push_back(n);
+            expected.push_back(n);
+
+            if n % 3 == 0 {
+                let (elem, next) = queue.pop_front().unwrap();
+                assert_eq!(Some(*elem), expected.pop_front());
+                queue = next;
+            }
+            assert_eq!(queue.len(), expected.len());
+        }
+
+        assert!(queue.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn persistent_queue_versions() {
+        let queue = (0..10).collect::<PersistentQueue<_>>();
+        let (_, popped) = queue.pop_front().unwrap();
+        let pushed_a = popped.push_back(10);
+        let pushed_b = popped.push_back(20);
+
+        // Older versions are left untouched
+        assert!(queue.iter().copied().eq(0..10));
+        assert!(popped.iter().copied().eq(1..10));
+        assert!(pushed_a.iter().copied().eq((1..10).chain([10])));
+        assert!(pushed_b.iter().copied().eq((1..10).chain([20])));
+
+        // Popping from the same version twice gives the same result
+        for _ in 0..2 {
+            let mut version = pushed_a.clone();
+            for n in (1..10).chain([10]) {
+                let (elem, next) = version.pop_front().unwrap();
+                assert_eq!(*elem, n);
+                version = next;
+            }
+            assert!(version.is_empty());
+        }
+    }
+
+    #[test]
+    fn persistent_queue_debug() {
+        let queue = (0..5).collect::<PersistentQueue<_>>().push_back(5);
+
+        assert_eq!(&format!("{queue:?}"), "[0, 1, 2, 3, 4, 5]");
+        assert_eq!(queue, (0..6).collect());
+        assert_ne!(queue, (0..5).collect());
+    }
+
+    #[test]
+    fn persistent_queue_iter_rear() {
+        let queue = (0..8).collect::<PersistentQueue<_>>();
+        assert_eq!(queue.rear.len(), 1);
+
+        // The rear is only collected once the iterator reaches it
+        let mut iter = queue.iter();
+        assert!(iter.by_ref().take(7).copied().eq(0..7));
+        assert!(iter.reversed.is_none());
+        assert_eq!(iter.next(), Some(&7));
+        assert_eq!(iter.reversed, Some(Vec::new()));
+        assert_eq!(iter.next(), None);
+
+        let queue = (0..6).fold(queue, |queue, n| queue.push_back(8 + n));
+        assert!(queue.iter().copied().eq(0..14));
+    }
+
+    #[test]
+    fn persistent_queue_drop() {
+        let counter = std::rc::Rc::default();
+        let queue = (0..10)
+            .map(|n| std::rc::Rc::new(DropCounter::new(&counter, vec![n])))
+            .collect::<PersistentQueue<_>>();
+
+        let (_, popped) = queue.pop_front().unwrap();
+        drop(queue);
+        assert_eq!(counter.get(), 1);
+
+        drop(popped);
+        assert_eq!(counter.get(), 10);
+    }
+
+    #[test]
+    fn persistent_queue_drop_long() {
+        // Dropping a long chain of pending nodes must not overflow the stack
+        let queue = (0..1_000_000).collect::<PersistentQueue<_>>();
+        let (_, queue) = queue.pop_front().unwrap();
+        assert_eq!(queue.peek_front(), Some(&1));
+        drop(queue);
+    }
+
+    #[test]
+    fn persistent_queue_rc() {
+        let counter = std::rc::Rc::default();
+        let queue = (0..10)
+            .map(|n| std::rc::Rc::new(DropCounter::new(&counter, vec![n])))
+            .collect::<PersistentQueue<_, crate::RcKind>>();
+
+        let (_, popped) = queue.pop_front().unwrap();
+        assert_eq!(popped.len(), 9);
+        drop(queue);
+        assert_eq!(counter.get(), 1);
+
+        drop(popped);
+        assert_eq!(counter.get(), 10);
+    }
+
+    #[test]
+    fn persistent_queue_threads() {
+        // Every thread races to build the same pending nodes, which are only built once
+        let queue = (0..1000).collect::<PersistentQueue<_>>().push_back(1000);
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| assert!(queue.iter().copied().eq(0..=1000)));
+            }
+        });
+    }
+
+    #[test]
+    fn real_time_queue_push_pop() {
+        let mut queue = RealTimeQueue::<_>::new();
+        assert_eq!(queue.pop_front(), None);
+        assert_eq!(queue.peek_front(), None);
+        assert!(queue.is_empty());
+
+        for n in 0..10 {
+            queue = queue.push_back(n);
+            assert_eq!(queue.peek_front(), Some(&0));
+        }
+        assert_eq!(queue.len(), 10);
+
+        for n in 0..10 {
+            let (elem, next) = queue.pop_front().unwrap();
+            assert_eq!(*elem, n);
+            queue = next;
+        }
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop_front(), None);
+    }
+
+    #[test]
+    fn real_time_queue_interleaved() {
+        let mut queue = RealTimeQueue::<_>::new();
+        let mut expected = std::collections::VecDeque::new();
+
+        for n in 0..1000 {
+            queue = queue.push_back(n);
+            expected.push_back(n);
+
+            if n % 3 == 0 {
+                let (elem, next) = queue.pop_front().unwrap();
+                assert_eq!(Some(*elem), expected.pop_front());
+                queue = next;
+            }
+            assert_eq!(queue.len(), expected.len());
+        }
+
+        assert!(queue.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn real_time_queue_versions() {
+        let queue = (0..10).collect::<RealTimeQueue<_>>();
+        let (_, popped) = queue.pop_front().unwrap();
+        let pushed_a = popped.push_back(10);
+        let pushed_b = popped.push_back(20);
+
+        assert!(queue.iter().copied().eq(0..10));
+        assert!(popped.iter().copied().eq(1..10));
+        assert!(pushed_a.iter().copied().eq((1..10).chain([10])));
+        assert!(pushed_b.iter().copied().eq((1..10).chain([20])));
+
+        for _ in 0..2 {
+            let mut version = pushed_b.clone();
+            for n in (1..10).chain([20]) {
+                let (elem, next) = version.pop_front().unwrap();
+                assert_eq!(*elem, n);
+                version = next;
+            }
+            assert!(version.is_empty());
+        }
+    }
+
+    #[test]
+    fn real_time_queue_schedule() {
+        // The schedule builds one node per operation, so the front is always fully built by the
+        // time a rotation starts
+        for n in 0..100 {
+            let queue = (0..n).collect::<RealTimeQueue<_>>();
+            let front_len = queue.len() - queue.rear.len();
+
+            let mut front = queue.front.clone();
+            let mut pending = 0;
+            while let Front::Lazy(suspension) = front {
+                if suspension.node.get().is_none() {
+                    pending += 1;
+                }
+                front = suspension.force().1.clone();
+            }
+            assert!(pending <= front_len - queue.rear.len());
+        }
+    }
+
+    #[test]
+    fn real_time_queue_drop() {
+        let counter = std::rc::Rc::default();
+        let queue = (0..10)
+            .map(|n| std::rc::Rc::new(DropCounter::new(&counter, vec![n])))
+            .collect::<RealTimeQueue<_>>();
+
+        let (_, popped) = queue.pop_front().unwrap();
+        drop(queue);
+        assert_eq!(counter.get(), 1);
+
+        drop(popped);
+        assert_eq!(counter.get(), 10);
+    }
+}
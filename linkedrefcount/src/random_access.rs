@@ -0,0 +1,557 @@
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::{ArcKind, LinkedRefCount, PointerKind, shared::Shared};
+
+/// A persistent list with O(1) [`prepend`](Self::prepend), [`head`](Self::head) and
+/// [`tail`](Self::tail), like a [`LinkedRefCount`], but also O(log n) [`get`](Self::get) and
+/// [`set`](Self::set). This is Okasaki's skew-binary random-access list.
+///
+/// Elements are stored in complete binary trees of size `2^k - 1`, each holding its elements in
+/// pre-order. The trees are kept in a [`LinkedRefCount`] from smallest to largest, where only the
+/// first two may have the same size, which is the skew-binary representation of the length of
+/// the list. There are O(log n) trees of height O(log n), which bounds the cost of an index
+/// lookup.
+///
+/// Tree nodes are reference-counted with `K` just like the nodes of a [`LinkedRefCount`], so
+/// versions of a list share every tree they have in common, and every subtree which an update did
+/// not go through.
+///
+/// Both tree nodes and the nodes of the spine are allocated with `A`, which defaults to the global
+/// allocator. Use [`new_in`](Self::new_in) to allocate them somewhere else.
+pub struct RandomAccessList<T, K: PointerKind = ArcKind, A: Allocator = Global> {
+    spine: LinkedRefCount<Digit<T, K, A>, K, A>,
+    size: usize,
+}
+
+/// A complete binary tree of `size` elements.
+struct Digit<T, K: PointerKind, A: Allocator> {
+    size: usize,
+    tree: Subtree<T, K, A>,
+}
+
+type Subtree<T, K, A> = Shared<Tree<T, K, A>, K, A>;
+
+/// The left and right children of a tree node.
+type Children<T, K, A> = (Subtree<T, K, A>, Subtree<T, K, A>);
+
+/// A node of a complete binary tree. The children have the same size, and are either both there
+/// or both missing.
+struct Tree<T, K: PointerKind, A: Allocator> {
+    elem: T,
+    children: Option<Children<T, K, A>>,
+}
+
+impl<T, K: PointerKind, A: Allocator + Clone> Clone for Digit<T, K, A> {
+    fn clone(&self) -> Self {
+        Self {
+            size: self.size,
+            tree: self.tree.clone(),
+        }
+    }
+}
+
+impl<T, K: PointerKind, A: Allocator> Digit<T, K, A> {
+    fn leaf(elem: T, alloc: A) -> Self {
+        Self {
+            size: 1,
+            tree: Shared::new_in(
+                Tree {
+                    elem,
+                    children: None,
+                },
+                alloc,
+            ),
+        }
+    }
+
+    /// Returns a reference to the `n`th element of the tree, in pre-order. `n` must be in bounds.
+    fn get(&self, mut n: usize) -> &T {
+        let mut size = self.size;
+        let mut tree = &*self.tree;
+
+        while n > 0 {
+            let (left, right) = tree
+                .children
+                .as_ref()
+                .expect("Only trees with more than one element have an index above 0");
+            size /= 2;
+
+            if n <= size {
+                tree = left;
+                n -= 1;
+            } else {
+                tree = right;
+                n -= 1 + size;
+            }
+        }
+
+        &tree.elem
+    }
+
+    /// Replaces the `n`th element of the tree, in pre-order. `n` must be in bounds.
+    ///
+    /// Only the nodes on the path to the element are copied, with `alloc`, and only if they are
+    /// shared.
+    fn set(&mut self, mut n: usize, elem: T, alloc: &A)
+    where
+        T: Clone,
+        A: Clone,
+    {
+        let mut size = self.size;
+        let mut tree = make_unique(&mut self.tree, alloc);
+
+        while n > 0 {
+            let (left, right) = tree
+                .children
+                .as_mut()
+                .expect("Only trees with more than one element have an index above 0");
+            size /= 2;
+
+            if n <= size {
+                tree = make_unique(left, alloc);
+                n -= 1;
+            } else {
+                tree = make_unique(right, alloc);
+                n -= 1 + size;
+            }
+        }
+
+        tree.elem = elem;
+    }
+}
+
+/// Returns a mutable reference to the tree node, replacing it with a copy allocated with `alloc`
+/// first if it is shared.
+fn make_unique<'a, T: Clone, K: PointerKind, A: Allocator + Clone>(
+    tree: &'a mut Subtree<T, K, A>,
+    alloc: &A,
+) -> &'a mut Tree<T, K, A> {
+    if Shared::get_mut(tree).is_none() {
+        *tree = Shared::new_in(
+            Tree {
+                elem: tree.elem.clone(),
+                children: tree.children.clone(),
+            },
+            alloc.clone(),
+        );
+    }
+
+    Shared::get_mut(tree).expect("The node is either unique or was just copied")
+}
+
+impl<T: std::fmt::Debug, K: PointerKind, A: Allocator> std::fmt::Debug
+    for RandomAccessList<T, K, A>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Cloning a list is O(1): the clone shares every tree with the original.
+impl<T, K: PointerKind, A: Allocator + Clone> Clone for RandomAccessList<T, K, A> {
+    fn clone(&self) -> Self {
+        Self {
+            spine: self.spine.clone(),
+            size: self.size,
+        }
+    }
+}
+
+impl<T: PartialEq, K: PointerKind, A: Allocator> PartialEq for RandomAccessList<T, K, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, K: PointerKind, A: Allocator> Eq for RandomAccessList<T, K, A> {}
+
+impl<T, K: PointerKind> Default for RandomAccessList<T, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds a list whose head is the first element yielded by the iterator, so iterating over the
+/// list yields the elements back in the same order.
+impl<T, K: PointerKind> FromIterator<T> for RandomAccessList<T, K> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems = iter.into_iter().collect::<Vec<_>>();
+        elems
+            .into_iter()
+            .rev()
+            .fold(Self::new(), |list, elem| list.prepend(elem))
+    }
+}
+
+impl<'a, T, K: PointerKind, A: Allocator> IntoIterator for &'a RandomAccessList<T, K, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, K, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, K: PointerKind> RandomAccessList<T, K> {
+    pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, K: PointerKind, A: Allocator + Clone> RandomAccessList<T, K, A> {
+    /// Creates an empty list whose nodes are allocated with `alloc`.
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            spine: LinkedRefCount::new_in(alloc),
+            size: 0,
+        }
+    }
+
+    /// Returns a reference to the allocator used by the list.
+    pub fn allocator(&self) -> &A {
+        self.spine.allocator()
+    }
+
+    /// Returns a new list with `elem` in front of this one.
+    ///
+    /// This runs in O(1) time. If the first two trees have the same size, they become the
+    /// children of a new tree rooted at `elem`, otherwise `elem` is added as a tree of its own.
+    /// Either way every tree of this list is shared.
+    pub fn prepend(&self, elem: T) -> Self {
+        let alloc = self.allocator().clone();
+        let mut digits = self.spine.iter();
+
+        let spine = match (digits.next(), digits.next()) {
+            (Some(left), Some(right)) if left.size == right.size => {
+                self.spine.skip(2).prepend(Digit {
+                    size: 1 + left.size + right.size,
+                    tree: Shared::new_in(
+                        Tree {
+                            elem,
+                            children: Some((left.tree.clone(), right.tree.clone())),
+                        },
+                        alloc,
+                    ),
+                })
+            }
+            _ => self.spine.prepend(Digit::leaf(elem, alloc)),
+        };
+
+        Self {
+            spine,
+            size: self.size + 1,
+        }
+    }
+
+    /// Returns the list without its first element, or an empty list if this one is empty.
+    ///
+    /// This runs in O(1) time. The first tree is split into its two children, which are shared
+    /// along with every other tree.
+    pub fn tail(&self) -> Self {
+        let Some(digit) = self.spine.head() else {
+            return Self::new_in(self.allocator().clone());
+        };
+
+        let spine = match &digit.tree.children {
+            Some((left, right)) => {
+                let size = digit.size / 2;
+                self.spine
+                    .tail()
+                    .prepend(Digit {
+                        size,
+                        tree: right.clone(),
+                    })
+                    .prepend(Digit {
+                        size,
+                        tree: left.clone(),
+                    })
+            }
+            None => self.spine.tail(),
+        };
+
+        Self {
+            spine,
+            size: self.size - 1,
+        }
+    }
+
+    /// Returns the list with its `n`th element replaced by `elem`. This runs in O(log n) time.
+    ///
+    /// Only the path to the element is copied: the trees in front of it in the spine, and the
+    /// nodes above it in its tree. Everything else is shared. Nodes which are not shared with any
+    /// other list are updated in place instead, the same as with [`LinkedRefCount::set`]. Use
+    /// [`clone`](Clone::clone) first to keep the original.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is out of bounds.
+    pub fn set(self, mut n: usize, elem: T) -> Self
+    where
+        T: Clone,
+    {
+        assert!(
+            n < self.size,
+            "Cannot set element {n}, list only has {} elements",
+            self.size
+        );
+
+        let Self { spine, size } = self;
+        let alloc = spine.allocator().clone();
+        let index = spine
+            .iter()
+            .position(|digit| {
+                if n < digit.size {
+                    true
+                } else {
+                    n -= digit.size;
+                    false
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            spine: spine.update(index, |digit| digit.set(n, elem, &alloc)),
+            size,
+        }
+    }
+}
+
+impl<T, K: PointerKind, A: Allocator> RandomAccessList<T, K, A> {
+    /// Returns a reference to the first element, or [`None`] if the list is empty.
+    pub fn head(&self) -> Option<&T> {
+        self.spine.head().map(|digit| &digit.tree.elem)
+    }
+
+    /// Returns a reference to the `n`th element, or [`None`] if `n` is out of bounds. This runs in
+    /// O(log n) time.
+    pub fn get(&self, mut n: usize) -> Option<&T> {
+        for digit in self.spine.iter() {
+            if n < digit.size {
+                return Some(digit.get(n));
+            }
+            n -= digit.size;
+        }
+
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Iterates over the elements of the list from head to tail.
+    pub fn iter(&self) -> Iter<'_, T, K, A> {
+        Iter {
+            spine: self.spine.iter(),
+            stack: Vec::new(),
+        }
+    }
+}
+
+/// Iterator over the elements of a [`RandomAccessList`], from head to tail.
+pub struct Iter<'a, T, K: PointerKind = ArcKind, A: Allocator = Global> {
+    spine: crate::Iter<'a, Digit<T, K, A>, K, A>,
+    /// Subtrees of the current tree which are left to visit, in reverse order.
+    stack: Vec<&'a Tree<T, K, A>>,
+}
+
+impl<'a, T, K: PointerKind, A: Allocator> Iterator for Iter<'a, T, K, A> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stack.is_empty() {
+            self.stack.push(&self.spine.next()?.tree);
+        }
+
+        let tree = self
+            .stack
+            .pop()
+            .expect("The stack was just filled up if it was empty");
+        if let Some((left, right)) = &tree.children {
+            self.stack.push(right);
+            self.stack.push(left);
+        }
+
+        Some(&tree.elem)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dsa_util::{DropCounter, TrackingAllocator};
+
+    use super::*;
+    use crate::RcKind;
+
+    /// Returns the size of each tree of the list, in order.
+    fn sizes<T, K: PointerKind, A: Allocator>(list: &RandomAccessList<T, K, A>) -> Vec<usize> {
+        list.spine.iter().map(|digit| digit.size).collect()
+    }
+
+    #[test]
+    fn random_access_list_prepend() {
+        let mut list = RandomAccessList::<i32>::new();
+        assert_eq!(list.head(), None);
+        assert!(list.is_empty());
+
+        for n in (0..6).rev() {
+            list = list.prepend(n);
+            assert_eq!(list.head(), Some(&n));
+        }
+
+        assert_eq!(list.len(), 6);
+        assert_eq!(sizes(&list), [3, 3]);
+        assert!(list.iter().copied().eq(0..6));
+
+        // The first two trees have the same size so they are merged
+        let list = list.prepend(-1);
+        assert_eq!(sizes(&list), [7]);
+        assert!(list.iter().copied().eq(-1..6));
+
+        let list = list.prepend(-2);
+        assert_eq!(sizes(&list), [1, 7]);
+    }
+
+    #[test]
+    fn random_access_list_tail() {
+        let list = (0..100).collect::<RandomAccessList<_>>();
+
+        let mut tail = list.clone();
+        for n in 0..100 {
+            assert_eq!(tail.head(), Some(&n));
+            assert_eq!(tail.len(), 100 - n as usize);
+            assert!(tail.iter().copied().eq(n..100));
+            tail = tail.tail();
+        }
+
+        assert!(tail.is_empty());
+        assert!(tail.tail().is_empty());
+        assert!(list.iter().copied().eq(0..100));
+    }
+
+    #[test]
+    fn random_access_list_get() {
+        for len in 0..100 {
+            let list = (0..len).collect::<RandomAccessList<_>>();
+
+            for n in 0..len {
+                assert_eq!(list.get(n), Some(&n));
+            }
+            assert_eq!(list.get(len), None);
+        }
+    }
+
+    #[test]
+    fn random_access_list_set() {
+        let list = (0..100).collect::<RandomAccessList<_>>();
+
+        let mut set = list.clone();
+        for n in (0..100).step_by(7) {
+            set = set.set(n, n * 10);
+        }
+
+        for n in 0..100 {
+            let expected = if n % 7 == 0 { n * 10 } else { n };
+            assert_eq!(set.get(n), Some(&expected));
+            assert_eq!(list.get(n), Some(&n));
+        }
+    }
+
+    #[test]
+    fn random_access_list_set_sharing() {
+        let list = (0..17).collect::<RandomAccessList<_>>();
+        assert_eq!(sizes(&list), [1, 1, 15]);
+
+        // Only the last tree is copied, along the path to the element
+        let set = list.clone().set(7, 70);
+        let trees = list.spine.iter().zip(set.spine.iter()).collect::<Vec<_>>();
+        assert!(Shared::ptr_eq(&trees[0].0.tree, &trees[0].1.tree));
+        assert!(Shared::ptr_eq(&trees[1].0.tree, &trees[1].1.tree));
+        assert!(!Shared::ptr_eq(&trees[2].0.tree, &trees[2].1.tree));
+
+        let (lhs, rhs) = (&trees[2].0.tree, &trees[2].1.tree);
+        let (lhs_left, lhs_right) = lhs.children.as_ref().unwrap();
+        let (rhs_left, rhs_right) = rhs.children.as_ref().unwrap();
+        assert!(!Shared::ptr_eq(lhs_left, rhs_left));
+        assert!(Shared::ptr_eq(lhs_right, rhs_right));
+
+        assert_eq!(set.get(7), Some(&70));
+        assert_eq!(list.get(7), Some(&7));
+    }
+
+    #[test]
+    fn random_access_list_set_in_place() {
+        let list = (0..14).collect::<RandomAccessList<_>>();
+        assert_eq!(sizes(&list), [7, 7]);
+        let tree = &*list.spine.nth(1).unwrap().tree as *const Tree<_, _, _>;
+
+        // Nobody else holds the list, so the tree is updated in place
+        let list = list.set(12, 120);
+        assert_eq!(
+            &*list.spine.nth(1).unwrap().tree as *const Tree<_, _, _>,
+            tree
+        );
+        assert_eq!(list.get(12), Some(&120));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot set element 10, list only has 10 elements")]
+    fn random_access_list_set_out_of_bounds() {
+        (0..10).collect::<RandomAccessList<_>>().set(10, 0);
+    }
+
+    #[test]
+    fn random_access_list_debug() {
+        let list = (0..10).collect::<RandomAccessList<_>>();
+
+        assert_eq!(&format!("{list:?}"), "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
+        assert_eq!(list, (0..10).collect());
+        assert_ne!(list, list.tail());
+    }
+
+    #[test]
+    fn random_access_list_drop() {
+        let counter = std::rc::Rc::default();
+        let list = (0..10)
+            .map(|n| DropCounter::new(&counter, vec![n]))
+            .collect::<RandomAccessList<_, RcKind>>();
+
+        let tail = list.tail().tail();
+        drop(list);
+        assert_eq!(counter.get(), 2);
+
+        drop(tail);
+        assert_eq!(counter.get(), 10);
+    }
+
+    #[test]
+    fn random_access_list_allocator() {
+        let alloc = TrackingAllocator::new();
+
+        // Each element gets a tree node, and each tree a spine node
+        let list = (0..3).rev().fold(
+            RandomAccessList::<_, ArcKind, _>::new_in(&alloc),
+            |list, n| list.prepend(n),
+        );
+        assert_eq!(sizes(&list), [3]);
+        assert_eq!(alloc.allocations(), 6);
+        assert_eq!(alloc.live(), 4);
+
+        // Copies made by set and tail come from the same allocator
+        let shared = list.clone();
+        let list = list.set(2, 10);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [0, 1, 10]);
+        assert_eq!(alloc.live(), 7);
+
+        let tail = list.tail();
+        assert_eq!(sizes(&tail), [1, 1]);
+        assert_eq!(alloc.live(), 9);
+
+        drop((list, shared, tail));
+        assert_eq!(alloc.live(), 0);
+    }
+}
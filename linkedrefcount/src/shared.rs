@@ -0,0 +1,131 @@
+use allocator_api2::{
+    alloc::{Allocator, Global},
+    boxed::Box,
+};
+
+use crate::kind::PointerKind;
+
+/// A reference-counted pointer, used for the nodes of a [`LinkedRefCount`](crate::LinkedRefCount).
+///
+/// This is a trimmed down [`std::rc::Rc`] or [`std::sync::Arc`], depending on `K`, which allocates
+/// with `A` since `Arc::new_in` is not available on stable yet. There are no weak references,
+/// which makes uniqueness checks a single load.
+pub(crate) struct Shared<T, K: PointerKind, A: Allocator = Global> {
+    ptr: std::ptr::NonNull<Inner<T, K>>,
+    alloc: A,
+    _boo: std::marker::PhantomData<Inner<T, K>>,
+}
+
+struct Inner<T, K: PointerKind> {
+    count: K::Count,
+    value: T,
+}
+
+// SAFETY: same as `Arc`, the value can be dropped or accessed from any thread holding a pointer.
+// This only holds if the count itself can be updated from several threads, which is what keeps
+// `RcKind` pointers on a single thread
+unsafe impl<T: Send + Sync, K: PointerKind, A: Allocator + Send> Send for Shared<T, K, A> where
+    K::Count: Sync
+{
+}
+unsafe impl<T: Send + Sync, K: PointerKind, A: Allocator + Sync> Sync for Shared<T, K, A> where
+    K::Count: Sync
+{
+}
+
+impl<T, K: PointerKind, A: Allocator> Shared<T, K, A> {
+    pub(crate) fn new_in(value: T, alloc: A) -> Self {
+        let inner = Inner {
+            count: K::new_count(),
+            value,
+        };
+        let (ptr, alloc) = Box::into_non_null(Box::new_in(inner, alloc));
+
+        Self {
+            ptr,
+            alloc,
+            _boo: std::marker::PhantomData,
+        }
+    }
+
+    fn inner(&self) -> &Inner<T, K> {
+        // SAFETY: the allocation is kept alive for as long as there is a pointer to it
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns a mutable reference to the value if this is the only pointer to it.
+    pub(crate) fn get_mut(this: &mut Self) -> Option<&mut T> {
+        // The load syncs with the decrement in `drop` so that any access made through other
+        // pointers happens before we hand out a mutable reference
+        if K::load(&this.inner().count) == 1 {
+            Some(unsafe { &mut (*this.ptr.as_ptr()).value })
+        } else {
+            None
+        }
+    }
+
+    /// Moves the value out if this is the only pointer to it, or hands the pointer back otherwise.
+    pub(crate) fn try_unwrap(this: Self) -> Result<T, Self> {
+        if !K::try_claim(&this.inner().count) {
+            return Err(this);
+        }
+
+        let this = std::mem::ManuallyDrop::new(this);
+        // SAFETY: we just took the last reference, and `this` is never used again so moving the
+        // allocator out of it is fine
+        unsafe {
+            let alloc = std::ptr::read(&this.alloc);
+            Ok(Box::into_inner(Box::from_raw_in(this.ptr.as_ptr(), alloc)).value)
+        }
+    }
+
+    /// Returns `true` if both pointers point to the same allocation.
+    pub(crate) fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+
+    /// Returns the number of pointers to the allocation.
+    pub(crate) fn strong_count(this: &Self) -> usize {
+        K::load(&this.inner().count)
+    }
+}
+
+impl<T, K: PointerKind, A: Allocator + Clone> Clone for Shared<T, K, A> {
+    fn clone(&self) -> Self {
+        let count = K::increment(&self.inner().count);
+
+        // Leaking pointers in a loop could otherwise overflow the count
+        if count > isize::MAX as usize {
+            std::process::abort();
+        }
+
+        Self {
+            ptr: self.ptr,
+            alloc: self.alloc.clone(),
+            _boo: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T, K: PointerKind, A: Allocator> std::ops::Deref for Shared<T, K, A> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner().value
+    }
+}
+
+impl<T, K: PointerKind, A: Allocator> Drop for Shared<T, K, A> {
+    fn drop(&mut self) {
+        if K::decrement(&self.inner().count) != 1 {
+            return;
+        }
+
+        // Syncs with the decrement on every other pointer, so that all of their accesses happen
+        // before the value is dropped
+        K::acquire();
+
+        // SAFETY: this was the last pointer to the allocation, which was made by `alloc`
+        drop(unsafe { Box::from_raw_in(self.ptr.as_ptr(), &self.alloc) });
+    }
+}
@@ -0,0 +1,403 @@
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+/// A lock-free stack which can be pushed to and popped from by many threads at once.
+///
+/// This is a Treiber stack: the head of the stack is a single atomic pointer, and every push or pop
+/// retries a compare-and-swap on it until it succeeds.
+///
+/// The hard part is knowing when a popped node can be freed, since other threads which read the
+/// same head before it was popped may still be about to read its `next` pointer. Each call to
+/// [`pop`](Self::pop) registers itself in a counter for as long as it might touch a node. A popped
+/// node is only freed right away when no other pop is in flight; otherwise it is put on a pending
+/// list, which is freed by the last pop to leave once it is sure no other pop started in the
+/// meantime. Under constant contention the pending list can keep growing until pops quiet down,
+/// but no node is ever freed while another thread may read it.
+pub struct ConcurrentStack<T> {
+    head: AtomicPtr<Node<T>>,
+    size: AtomicUsize,
+    /// Number of threads currently inside of `pop`.
+    poppers: AtomicUsize,
+    /// Popped nodes which could not be freed yet, chained through their `next` pointer.
+    pending: AtomicPtr<Node<T>>,
+    _boo: std::marker::PhantomData<T>,
+}
+
+struct Node<T> {
+    /// Moved out by `pop` before the node is freed, so it is never dropped with the node.
+    elem: std::mem::ManuallyDrop<T>,
+    /// Atomic since it can be read by a pop which lost the race for this node while the winner
+    /// reuses it to chain the node onto the pending list.
+    next: AtomicPtr<Node<T>>,
+}
+
+// SAFETY: elements are moved in by one thread and out by another, but never shared
+unsafe impl<T: Send> Send for ConcurrentStack<T> {}
+unsafe impl<T: Send> Sync for ConcurrentStack<T> {}
+
+impl<T> std::fmt::Debug for ConcurrentStack<T> {
+    /// Elements cannot be shown since other threads may pop them while they are being formatted.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConcurrentStack")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Drop for ConcurrentStack<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+
+        // No other thread can be popping since we have a unique reference
+        unsafe { free_list(*self.pending.get_mut()) };
+    }
+}
+
+impl<T> Default for ConcurrentStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for ConcurrentStack<T> {
+    /// Pushes each element in turn, so the last element yielded ends up on top of the stack.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let stack = Self::new();
+        for elem in iter {
+            stack.push(elem);
+        }
+        stack
+    }
+}
+
+impl<T> ConcurrentStack<T> {
+    pub fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(std::ptr::null_mut()),
+            size: AtomicUsize::new(0),
+            poppers: AtomicUsize::new(0),
+            pending: AtomicPtr::new(std::ptr::null_mut()),
+            _boo: std::marker::PhantomData,
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Box::into_raw(Box::new(Node {
+            elem: std::mem::ManuallyDrop::new(elem),
+            next: AtomicPtr::new(std::ptr::null_mut()),
+        }));
+
+        // Counted before the node is published, so that a pop which takes it right away cannot
+        // decrement the size below zero
+        self.size.fetch_add(1, Ordering::Relaxed);
+
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: the node is not visible to other threads until the swap below succeeds
+            unsafe { (*node).next.store(head, Ordering::Relaxed) };
+
+            // Release publishes the node's contents to whichever thread pops it
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        self.poppers.fetch_add(1, Ordering::SeqCst);
+
+        // The head is read and swapped with SeqCst, like `poppers`, so that these accesses and the
+        // `poppers` check in `release` fall in a single total order. A pop which reads a node
+        // before it is unlinked is then registered by the time the winner checks `poppers`, and
+        // that winner defers the node instead of freeing it. With Acquire alone the check could be
+        // satisfied while another pop is still about to dereference the node
+        let mut head = self.head.load(Ordering::SeqCst);
+        while !head.is_null() {
+            // SAFETY: `head` cannot be freed while we are registered in `poppers`
+            let next = unsafe { (*head).next.load(Ordering::Relaxed) };
+
+            match self
+                .head
+                .compare_exchange_weak(head, next, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+
+        let elem = (!head.is_null()).then(|| {
+            self.size.fetch_sub(1, Ordering::Relaxed);
+            // SAFETY: winning the swap gives us sole ownership of the element, which is never
+            // read again since nodes are freed without dropping it
+            unsafe { std::mem::ManuallyDrop::take(&mut (*head).elem) }
+        });
+
+        unsafe { self.release(head) };
+
+        elem
+    }
+
+    /// Returns the number of elements in the stack. Other threads may push or pop at any time, so
+    /// this is only a snapshot which can be out of date by the time it is returned. It may also
+    /// count elements which are still being pushed, but never the ones already popped.
+    pub fn len(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if the stack is empty. Like [`len`](Self::len), this is only a snapshot.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Relaxed).is_null()
+    }
+
+    /// Unregisters the current thread from `poppers`, freeing `node` and any pending nodes if it
+    /// can prove no other pop may still read them.
+    ///
+    /// # Safety
+    ///
+    /// Must be called exactly once per call to `pop`, with the node it popped or null.
+    unsafe fn release(&self, node: *mut Node<T>) {
+        if self.poppers.load(Ordering::SeqCst) == 1 {
+            // We are the only pop in flight, so nobody else can have read any pending node. Take
+            // the whole list before unregistering so that new pops cannot see it either
+            let pending = self.pending.swap(std::ptr::null_mut(), Ordering::SeqCst);
+
+            if self.poppers.fetch_sub(1, Ordering::SeqCst) == 1 {
+                unsafe { free_list(pending) };
+            } else if !pending.is_null() {
+                // Other pops started after we checked `poppers`, and the list may hold a node one
+                // of them deferred while another was still reading it, so it has to wait
+                unsafe { self.defer(pending) };
+            }
+
+            // `node` was already unlinked when we checked `poppers`, and any pop which read it
+            // before then was still registered, so nobody else can be holding on to it
+            if !node.is_null() {
+                drop(unsafe { Box::from_raw(node) });
+            }
+        } else {
+            if !node.is_null() {
+                unsafe {
+                    (*node).next.store(std::ptr::null_mut(), Ordering::Relaxed);
+                    self.defer(node);
+                }
+            }
+            self.poppers.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Adds the chain of nodes starting at `first` to the pending list.
+    ///
+    /// # Safety
+    ///
+    /// The chain must be owned by the caller and be null-terminated.
+    unsafe fn defer(&self, first: *mut Node<T>) {
+        let mut last = first;
+        unsafe {
+            loop {
+                let next = (*last).next.load(Ordering::Relaxed);
+                if next.is_null() {
+                    break;
+                }
+                last = next;
+            }
+
+            let mut pending = self.pending.load(Ordering::Relaxed);
+            loop {
+                (*last).next.store(pending, Ordering::Relaxed);
+                match self.pending.compare_exchange_weak(
+                    pending,
+                    first,
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(current) => pending = current,
+                }
+            }
+        }
+    }
+}
+
+/// Frees every node of a null-terminated chain, without dropping their elements.
+///
+/// # Safety
+///
+/// The chain must be owned by the caller and its elements must have been moved out.
+unsafe fn free_list<T>(mut node: *mut Node<T>) {
+    while !node.is_null() {
+        let boxed = unsafe { Box::from_raw(node) };
+        node = boxed.next.load(Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dsa_util::SyncDropCounter;
+
+    use super::*;
+
+    #[test]
+    fn concurrent_stack_push_pop() {
+        let stack = ConcurrentStack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+
+        for n in 0..10 {
+            stack.push(n);
+        }
+        assert_eq!(stack.len(), 10);
+        assert!(!stack.is_empty());
+
+        for n in (0..10).rev() {
+            assert_eq!(stack.pop(), Some(n));
+        }
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+        assert_eq!(stack.len(), 0);
+    }
+
+    #[test]
+    fn concurrent_stack_from_iter() {
+        let stack = (0..10).collect::<ConcurrentStack<_>>();
+        assert_eq!(stack.len(), 10);
+        assert_eq!(stack.pop(), Some(9));
+        assert_eq!(format!("{stack:?}"), "ConcurrentStack { len: 9, .. }");
+    }
+
+    #[test]
+    fn concurrent_stack_drop() {
+        let counter = std::sync::Arc::default();
+        let stack = ConcurrentStack::new();
+
+        for n in 0..10 {
+            stack.push(SyncDropCounter::new(&counter, n));
+        }
+        drop(stack.pop());
+        drop(stack.pop());
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+
+        drop(stack);
+        assert_eq!(counter.load(Ordering::Relaxed), 10);
+    }
+
+    const THREADS: usize = 8;
+    const PER_THREAD: usize = 10_000;
+
+    #[test]
+    fn concurrent_stack_stress_push_then_pop() {
+        let counter = std::sync::Arc::default();
+        let stack = ConcurrentStack::new();
+
+        std::thread::scope(|s| {
+            for t in 0..THREADS {
+                let (stack, counter) = (&stack, &counter);
+                s.spawn(move || {
+                    for n in 0..PER_THREAD {
+                        stack.push((t * PER_THREAD + n, SyncDropCounter::new(counter, ())));
+                    }
+                });
+            }
+        });
+        assert_eq!(stack.len(), THREADS * PER_THREAD);
+
+        let popped = std::thread::scope(|s| {
+            let handles = (0..THREADS)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut popped = Vec::new();
+                        while let Some((n, _)) = stack.pop() {
+                            popped.push(n);
+                        }
+                        popped
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert_popped_once(popped, THREADS * PER_THREAD);
+        assert_eq!(counter.load(Ordering::Relaxed), THREADS * PER_THREAD);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn concurrent_stack_stress_interleaved() {
+        let counter = std::sync::Arc::default();
+        let stack = ConcurrentStack::new();
+
+        // Every thread pushes and pops at the same time, so that nodes are constantly freed
+        // while other threads are racing to pop them
+        let popped = std::thread::scope(|s| {
+            let handles = (0..THREADS)
+                .map(|t| {
+                    let (stack, counter) = (&stack, &counter);
+                    s.spawn(move || {
+                        let mut popped = Vec::new();
+                        for n in 0..PER_THREAD {
+                            stack.push((t * PER_THREAD + n, SyncDropCounter::new(counter, ())));
+                            if n % 3 != 0
+                                && let Some((n, _)) = stack.pop()
+                            {
+                                popped.push(n);
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let remaining = stack.len();
+        assert_eq!(popped.len() + remaining, THREADS * PER_THREAD);
+        assert_eq!(counter.load(Ordering::Relaxed), popped.len());
+
+        let mut all = popped;
+        while let Some((n, _)) = stack.pop() {
+            all.push(n);
+        }
+        assert_popped_once(all, THREADS * PER_THREAD);
+        assert_eq!(counter.load(Ordering::Relaxed), THREADS * PER_THREAD);
+    }
+
+    #[test]
+    fn concurrent_stack_stress_drop_while_full() {
+        let counter = std::sync::Arc::default();
+
+        {
+            let stack = ConcurrentStack::new();
+            std::thread::scope(|s| {
+                for _ in 0..THREADS {
+                    s.spawn(|| {
+                        for n in 0..PER_THREAD {
+                            stack.push(SyncDropCounter::new(&counter, ()));
+                            if n % 2 == 0 {
+                                drop(stack.pop());
+                            }
+                        }
+                    });
+                }
+            });
+        }
+
+        assert_eq!(counter.load(Ordering::Relaxed), THREADS * PER_THREAD);
+    }
+
+    /// Checks that every element from `0..count` was popped exactly once.
+    fn assert_popped_once(mut popped: Vec<usize>, count: usize) {
+        popped.sort_unstable();
+        assert_eq!(popped.len(), count);
+        assert!(popped.iter().copied().eq(0..count));
+    }
+}
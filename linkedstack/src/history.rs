@@ -0,0 +1,344 @@
+use crate::LinkedStack;
+
+/// An undo/redo history of commands, as found in editors.
+///
+/// Commands are [`record`](Self::record)ed onto an undo stack. [`undo`](Self::undo) moves the most
+/// recent entry over to a redo stack, and [`redo`](Self::redo) moves it back. Recording a new
+/// command after an undo discards the redo stack, since it belongs to a branch of the history
+/// which is no longer reachable.
+///
+/// Each entry is a transaction: a single command, or every command recorded between
+/// [`begin_group`](Self::begin_group) and [`end_group`](Self::end_group). The history never applies
+/// commands itself, it only hands back the ones to undo or redo.
+///
+/// The number of entries can be bounded with [`with_max_depth`](Self::with_max_depth), in which
+/// case the oldest entries are evicted once it is exceeded.
+#[derive(Debug, Clone)]
+pub struct UndoHistory<Cmd> {
+    /// Entries to undo, oldest first. This is a deque rather than a stack so that the oldest
+    /// entry can be evicted in O(1) time.
+    undo: std::collections::VecDeque<Vec<Cmd>>,
+    redo: LinkedStack<Vec<Cmd>>,
+    max_depth: usize,
+    /// Number of nested groups currently open.
+    groups: usize,
+    /// Whether the open group has already pushed its entry onto the undo stack.
+    group_started: bool,
+}
+
+impl<Cmd> Default for UndoHistory<Cmd> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Cmd> UndoHistory<Cmd> {
+    /// Creates an empty history with no bound on the number of entries.
+    pub fn new() -> Self {
+        Self::with_max_depth(usize::MAX)
+    }
+
+    /// Creates an empty history which keeps at most `max_depth` entries to undo.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_depth` is 0.
+    pub fn with_max_depth(max_depth: usize) -> Self {
+        assert!(
+            max_depth > 0,
+            "Cannot create an undo history with a max depth of 0"
+        );
+
+        Self {
+            undo: std::collections::VecDeque::new(),
+            redo: LinkedStack::new(),
+            max_depth,
+            groups: 0,
+            group_started: false,
+        }
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Returns the number of entries which can be undone.
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Returns the number of entries which can be redone.
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.undo_len() > 0 && self.groups == 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty() && self.groups == 0
+    }
+
+    /// Records a command which has just been applied.
+    ///
+    /// This discards every entry which could be redone. If a group is open, the command is added
+    /// to it, otherwise it becomes an entry of its own. If this pushes the number of entries past
+    /// the max depth, the oldest one is evicted and dropped.
+    pub fn record(&mut self, cmd: Cmd) {
+        self.redo = LinkedStack::new();
+
+        if self.group_started {
+            self.undo
+                .back_mut()
+                .expect("A started group always has its entry on top of the undo stack")
+                .push(cmd);
+            return;
+        }
+
+        self.group_started = self.groups > 0;
+        self.undo.push_back(vec![cmd]);
+
+        if self.undo.len() > self.max_depth {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Opens a group: every command recorded until the matching [`end_group`](Self::end_group) is
+    /// undone and redone as a single entry.
+    ///
+    /// Groups can be nested, in which case inner groups are merged into the outermost one.
+    pub fn begin_group(&mut self) {
+        self.groups += 1;
+    }
+
+    /// Closes the group opened by the last call to [`begin_group`](Self::begin_group). A group in
+    /// which no command was recorded does not create an entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no group is open.
+    pub fn end_group(&mut self) {
+        assert!(self.groups > 0, "Cannot end a group which was never begun");
+
+        self.groups -= 1;
+        if self.groups == 0 {
+            self.group_started = false;
+        }
+    }
+
+    /// Moves the most recent entry over to the redo stack and returns its commands, in the order
+    /// they were recorded. They should be reverted in reverse order. Returns [`None`] if there is
+    /// nothing to undo.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a group is open.
+    pub fn undo(&mut self) -> Option<&[Cmd]> {
+        assert!(self.groups == 0, "Cannot undo while a group is open");
+
+        let entry = self.undo.pop_back()?;
+        self.redo.push(entry);
+        self.redo.peek().map(Vec::as_slice)
+    }
+
+    /// Moves the most recently undone entry back to the undo stack and returns its commands, in
+    /// the order they were recorded. Returns [`None`] if there is nothing to redo.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a group is open.
+    pub fn redo(&mut self) -> Option<&[Cmd]> {
+        assert!(self.groups == 0, "Cannot redo while a group is open");
+
+        let entry = self.redo.pop()?;
+        self.undo.push_back(entry);
+        self.undo.back().map(Vec::as_slice)
+    }
+
+    /// Discards every entry, both to undo and to redo.
+    ///
+    /// Open groups are left open, so that a call to [`end_group`](Self::end_group) is still
+    /// expected for each of them. Commands recorded after clearing go to a new entry.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo = LinkedStack::new();
+        self.group_started = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dsa_util::DropCounter;
+
+    use super::*;
+
+    #[test]
+    fn undo_history_undo_redo() {
+        let mut history = UndoHistory::new();
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), None);
+
+        history.record(0);
+        history.record(1);
+        history.record(2);
+        assert_eq!(history.undo_len(), 3);
+        assert!(history.can_undo());
+        assert!(!history.can_redo());
+
+        assert_eq!(history.undo(), Some(&[2][..]));
+        assert_eq!(history.undo(), Some(&[1][..]));
+        assert_eq!(history.undo_len(), 1);
+        assert_eq!(history.redo_len(), 2);
+
+        assert_eq!(history.redo(), Some(&[1][..]));
+        assert_eq!(history.redo(), Some(&[2][..]));
+        assert_eq!(history.redo(), None);
+
+        assert_eq!(history.undo(), Some(&[2][..]));
+        assert_eq!(history.undo(), Some(&[1][..]));
+        assert_eq!(history.undo(), Some(&[0][..]));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo_len(), 3);
+    }
+
+    #[test]
+    fn undo_history_branch() {
+        let mut history = UndoHistory::new();
+        history.record(0);
+        history.record(1);
+        history.record(2);
+
+        history.undo();
+        history.undo();
+        history.record(3);
+        assert!(!history.can_redo());
+        assert_eq!(history.redo(), None);
+
+        assert_eq!(history.undo(), Some(&[3][..]));
+        assert_eq!(history.undo(), Some(&[0][..]));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn undo_history_max_depth() {
+        let mut history = UndoHistory::with_max_depth(3);
+        for n in 0..10 {
+            history.record(n);
+        }
+        assert_eq!(history.undo_len(), 3);
+
+        assert_eq!(history.undo(), Some(&[9][..]));
+        assert_eq!(history.undo(), Some(&[8][..]));
+        assert_eq!(history.undo(), Some(&[7][..]));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn undo_history_max_depth_eviction() {
+        let mut history = UndoHistory::with_max_depth(3);
+
+        for n in 0..100 {
+            history.record(n);
+            assert_eq!(history.undo_len(), (n + 1).min(3));
+        }
+
+        // Undoing and redoing does not bring evicted entries back
+        assert_eq!(history.undo(), Some(&[99][..]));
+        assert_eq!(history.undo(), Some(&[98][..]));
+        assert_eq!(history.undo(), Some(&[97][..]));
+        assert_eq!(history.undo(), None);
+        assert!(!history.can_undo());
+        assert_eq!(history.redo(), Some(&[97][..]));
+        history.record(100);
+        assert_eq!(history.undo_len(), 2);
+    }
+
+    #[test]
+    fn undo_history_max_depth_drop() {
+        let rc = std::rc::Rc::default();
+        let mut history = UndoHistory::with_max_depth(3);
+
+        // Evicted entries are dropped right away rather than lingering at the bottom
+        for n in 0..10_usize {
+            history.record(DropCounter::new(&rc, n));
+            assert_eq!(rc.get(), n.saturating_sub(2));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "max depth of 0")]
+    fn undo_history_max_depth_zero() {
+        UndoHistory::<()>::with_max_depth(0);
+    }
+
+    #[test]
+    fn undo_history_group() {
+        let mut history = UndoHistory::with_max_depth(2);
+        history.record(0);
+
+        history.begin_group();
+        history.record(1);
+        history.begin_group();
+        history.record(2);
+        history.end_group();
+        assert!(!history.can_undo());
+        history.record(3);
+        history.end_group();
+
+        // Empty groups are dropped
+        history.begin_group();
+        history.end_group();
+
+        history.record(4);
+        assert_eq!(history.undo_len(), 2);
+
+        assert_eq!(history.undo(), Some(&[4][..]));
+        assert_eq!(history.undo(), Some(&[1, 2, 3][..]));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(&[1, 2, 3][..]));
+    }
+
+    #[test]
+    #[should_panic(expected = "while a group is open")]
+    fn undo_history_group_undo() {
+        let mut history = UndoHistory::new();
+        history.begin_group();
+        history.record(0);
+        history.undo();
+    }
+
+    #[test]
+    fn undo_history_clear() {
+        let mut history = UndoHistory::new();
+        history.record(0);
+        history.record(1);
+        history.undo();
+
+        history.clear();
+        assert_eq!(history.undo_len(), 0);
+        assert_eq!(history.redo_len(), 0);
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn undo_history_clear_group() {
+        let mut history = UndoHistory::new();
+        history.record(0);
+
+        history.begin_group();
+        history.record(1);
+        history.clear();
+        assert_eq!(history.undo_len(), 0);
+
+        // The group survives, but starts a new entry
+        history.record(2);
+        history.record(3);
+        assert!(!history.can_undo());
+        history.end_group();
+
+        assert_eq!(history.undo_len(), 1);
+        assert_eq!(history.undo(), Some(&[2, 3][..]));
+        assert_eq!(history.undo(), None);
+    }
+}
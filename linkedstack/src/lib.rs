@@ -1,37 +1,134 @@
+use allocator_api2::{
+    alloc::{Allocator, Global},
+    boxed::Box,
+};
 use itertools::{
     FoldWhile::{Continue, Done},
     Itertools,
 };
 
-pub struct LinkedStack<T> {
-    head: Link<T>,
+pub mod concurrent;
+pub mod history;
+pub mod monoid;
+
+pub use concurrent::ConcurrentStack;
+pub use history::UndoHistory;
+pub use monoid::{Monoid, MonoidQueue, MonoidStack};
+
+/// A singly-linked stack.
+///
+/// Nodes are allocated with `A`, which defaults to the global allocator. Use
+/// [`new_in`](Self::new_in) to allocate them somewhere else, such as a bump arena.
+pub struct LinkedStack<T, A: Allocator = Global> {
+    head: Link<T, A>,
     size: usize,
+    pool: Pool<T, A>,
+    journal: Vec<Change<T, A>>,
+    alloc: A,
 }
 
-type Link<T> = Option<Box<Node<T>>>;
+type Link<T, A> = Option<Box<Node<T, A>, A>>;
 
-struct Node<T> {
+struct Node<T, A: Allocator> {
     elem: T,
-    next: Link<T>,
+    next: Link<T, A>,
+}
+
+/// A change made to a [`LinkedStack`] through a [`Checkpoint`], recorded so that it can be undone
+/// by [`rollback`](Checkpoint::rollback).
+enum Change<T, A: Allocator> {
+    Push,
+    /// Popped nodes are unlinked but kept alive, element and all, until the outermost checkpoint
+    /// has been committed.
+    Pop(Box<Node<T, A>, A>),
+}
+
+/// Free list of nodes released by a [`LinkedStack`], which are reused on the next push instead of
+/// allocating a new one. Pooled nodes are chained through their `next` link, so releasing a node
+/// never allocates. They have had their element moved out, so they must never be dropped as a
+/// [`Node`], only deallocated.
+///
+/// The capacity is only an upper bound on the number of pooled nodes: the free list starts empty
+/// and grows as nodes are released. A capacity of 0 disables recycling entirely.
+struct Pool<T, A: Allocator> {
+    free: Link<T, A>,
+    len: usize,
+    capacity: usize,
+}
+
+impl<T, A: Allocator> Pool<T, A> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            free: None,
+            len: 0,
+            capacity,
+        }
+    }
+
+    /// Unlinks the first pooled node, as uninitialized memory.
+    fn pop_free(&mut self) -> Option<Box<std::mem::MaybeUninit<Node<T, A>>, A>> {
+        let (node, alloc) = Box::into_raw_with_allocator(self.free.take()?);
+        // This is safe since only the element of a pooled node was moved out, its link is still
+        // there, and the node is not read as a `Node` again
+        self.free = unsafe { std::ptr::read(&raw const (*node).next) };
+        self.len -= 1;
+        Some(unsafe { Box::from_raw_in(node.cast::<std::mem::MaybeUninit<Node<T, A>>>(), alloc) })
+    }
+
+    /// Moves the element out of a node which has already been unlinked, keeping the node around
+    /// if the pool is not full yet.
+    fn release(&mut self, node: Box<Node<T, A>, A>) -> T {
+        if self.len < self.capacity {
+            let (node, alloc) = Box::into_raw_with_allocator(node);
+            // This is safe since the node is not read as a `Node` again once the element has been
+            // moved out, only overwritten by `alloc` or deallocated by `pop_free`. Overwriting the
+            // link drops the old one, which is always `None` for an unlinked node
+            unsafe {
+                let elem = std::ptr::read(&raw const (*node).elem);
+                (*node).next = self.free.take();
+                self.free = Some(Box::from_raw_in(node, alloc));
+                self.len += 1;
+                elem
+            }
+        } else {
+            Box::into_inner(node).elem
+        }
+    }
+}
+
+impl<T, A: Allocator + Clone> Pool<T, A> {
+    /// Creates a node from `elem` and `next`, reusing a pooled node if there is one.
+    fn alloc(&mut self, elem: T, next: Link<T, A>, alloc: &A) -> Box<Node<T, A>, A> {
+        match self.pop_free() {
+            Some(node) => Box::write(node, Node { elem, next }),
+            None => Box::new_in(Node { elem, next }, alloc.clone()),
+        }
+    }
+}
+
+impl<T, A: Allocator> Drop for Pool<T, A> {
+    fn drop(&mut self) {
+        while self.pop_free().is_some() {}
+    }
 }
 
-impl<T: std::fmt::Debug> std::fmt::Debug for LinkedStack<T> {
+impl<T: std::fmt::Debug, A: Allocator + Clone> std::fmt::Debug for LinkedStack<T, A> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_list().entries(self.iter()).finish()
     }
 }
 
-impl<T: Clone> Clone for LinkedStack<T> {
+impl<T: Clone, A: Allocator + Clone> Clone for LinkedStack<T, A> {
     fn clone(&self) -> Self {
         let mut iter = self.iter().cloned();
         let head = iter.next().and_then(|elem| {
-            let mut head = Some(Box::new(Node { elem, next: None }));
+            let mut head = Some(Box::new_in(Node { elem, next: None }, self.alloc.clone()));
             let mut cursor = head.as_mut();
 
             for elem in iter {
                 unsafe {
                     let inner = cursor.unwrap_unchecked();
-                    inner.next = Some(Box::new(Node { elem, next: None }));
+                    inner.next = Some(Box::new_in(Node { elem, next: None }, self.alloc.clone()));
                     cursor = inner.next.as_mut();
                 }
             }
@@ -42,11 +139,14 @@ impl<T: Clone> Clone for LinkedStack<T> {
         LinkedStack {
             head,
             size: self.size,
+            pool: Pool::new(self.pool.capacity),
+            journal: Vec::new(),
+            alloc: self.alloc.clone(),
         }
     }
 }
 
-impl<T: PartialEq> PartialEq for LinkedStack<T> {
+impl<T: PartialEq, A: Allocator + Clone> PartialEq for LinkedStack<T, A> {
     fn eq(&self, other: &Self) -> bool {
         self.size == other.size
             && self
@@ -62,9 +162,9 @@ impl<T: PartialEq> PartialEq for LinkedStack<T> {
     }
 }
 
-impl<T: Eq> Eq for LinkedStack<T> {}
+impl<T: Eq, A: Allocator + Clone> Eq for LinkedStack<T, A> {}
 
-impl<T> Drop for LinkedStack<T> {
+impl<T, A: Allocator> Drop for LinkedStack<T, A> {
     /// This drops each node _iteratively_ since we are replacing `node.next` with [`None`],
     /// meaning that the drop implementation for [`Box`] will not be called on it. Instead, each
     /// node is dropped when it goes out of scope at the end of each `while` iteration. Neat!
@@ -81,31 +181,156 @@ impl<T> Default for LinkedStack<T> {
     }
 }
 
+/// Pushes each element in turn, so the last element yielded by the iterator ends up on top of the
+/// stack. Use [`LinkedStack::from_iter_rev`] to keep the first element on top instead.
+impl<T> FromIterator<T> for LinkedStack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stack = Self::new();
+        stack.extend(iter);
+        stack
+    }
+}
+
+/// Pushes each element in turn, so the last element yielded by the iterator ends up on top of the
+/// stack.
+impl<T, A: Allocator + Clone> Extend<T> for LinkedStack<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, A: Allocator + Clone> Extend<&'a T> for LinkedStack<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
 impl<T> LinkedStack<T> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    /// Creates an empty stack which recycles up to `capacity` nodes.
+    ///
+    /// Nodes released by [`pop`](Self::pop), [`remove`](Self::remove), [`retain`](Self::retain)
+    /// and [`dedup`](Self::dedup) are kept on an internal free list instead of being deallocated,
+    /// as long as the list holds fewer than `capacity` nodes. Pushing or inserting an element
+    /// then reuses one of those nodes rather than allocating a new one. A capacity of 0, which is
+    /// what [`new`](Self::new) uses, disables recycling.
+    ///
+    /// The capacity is only an upper bound: no memory is reserved up front, and the free list
+    /// grows as nodes are released.
+    pub fn with_pool_capacity(capacity: usize) -> Self {
+        Self::with_pool_capacity_in(capacity, Global)
+    }
+
+    /// Creates a stack from an iterator, keeping the first element yielded on top. Popping the
+    /// stack then returns the elements in the order they were yielded.
+    pub fn from_iter_rev<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stack = Self::new();
+        let mut tail = &mut stack.head;
+
+        for elem in iter {
+            tail = &mut tail.insert(Box::new(Node { elem, next: None })).next;
+            stack.size += 1;
+        }
+
+        stack
+    }
+}
+
+impl<T, A: Allocator + Clone> LinkedStack<T, A> {
+    /// Creates an empty stack whose nodes are allocated with `alloc`.
+    pub fn new_in(alloc: A) -> Self {
+        Self::with_pool_capacity_in(0, alloc)
+    }
+
+    /// Creates an empty stack whose nodes are allocated with `alloc`, and which recycles up to
+    /// `capacity` of them, see [`with_pool_capacity`](LinkedStack::with_pool_capacity).
+    pub fn with_pool_capacity_in(capacity: usize, alloc: A) -> Self {
         Self {
             head: None,
             size: 0,
+            pool: Pool::new(capacity),
+            journal: Vec::new(),
+            alloc,
         }
     }
 
+    /// Returns a reference to the allocator used by the stack.
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Returns the maximum number of nodes kept around for reuse.
+    pub fn pool_capacity(&self) -> usize {
+        self.pool.capacity
+    }
+
+    /// Sets the maximum number of nodes kept around for reuse, deallocating any pooled nodes in
+    /// excess of the new capacity.
+    pub fn set_pool_capacity(&mut self, capacity: usize) {
+        while self.pool.len > capacity {
+            self.pool.pop_free();
+        }
+        self.pool.capacity = capacity;
+    }
+
+    /// Returns the number of nodes currently waiting to be reused.
+    pub fn pool_len(&self) -> usize {
+        self.pool.len
+    }
+
+    /// Deallocates every node waiting to be reused. The pool capacity is left unchanged, so the
+    /// pool will fill up again as elements are removed.
+    pub fn shrink_pool(&mut self) {
+        while self.pool.pop_free().is_some() {}
+    }
+
     pub fn push(&mut self, elem: T) {
-        let new_node = Box::new(Node {
-            elem,
-            next: self.head.take(),
-        });
+        let new_node = self.pool.alloc(elem, self.head.take(), &self.alloc);
         self.head = Some(new_node);
         self.size += 1;
     }
 
     pub fn pop(&mut self) -> Option<T> {
-        self.head.take().map(|node| {
-            self.head = node.next;
+        self.head.take().map(|mut node| {
+            self.head = node.next.take();
             self.size -= 1;
-            node.elem
+            self.pool.release(node)
         })
     }
 
+    /// Takes a checkpoint of the current state of the stack, which it can later be rolled back
+    /// to.
+    ///
+    /// The checkpoint borrows the stack until it is resolved, and elements can only be added and
+    /// removed through its [`push`](Checkpoint::push) and [`pop`](Checkpoint::pop), which are
+    /// recorded. Rolling back undoes those changes in O(k) time, where k is the number of
+    /// changes made since the checkpoint. A checkpoint which is dropped without being committed
+    /// is rolled back.
+    pub fn checkpoint(&mut self) -> Checkpoint<'_, T, A> {
+        // Drop whatever a leaked checkpoint left behind, it can never be rolled back now
+        self.release_held();
+
+        Checkpoint {
+            journal_len: 0,
+            outermost: true,
+            stack: self,
+        }
+    }
+
+    /// Drops the elements held by the journal and forgets about the changes it recorded.
+    fn release_held(&mut self) {
+        for change in self.journal.drain(..) {
+            if let Change::Pop(node) = change {
+                self.pool.release(node);
+            }
+        }
+    }
+
     pub fn peek(&self) -> Option<&T> {
         self.head.as_ref().map(|node| &node.elem)
     }
@@ -122,6 +347,291 @@ impl<T> LinkedStack<T> {
         self.size == 0
     }
 
+    /// Reverses the order of the stack in place, so that the bottom element ends up on top.
+    ///
+    /// This runs in O(n) time and does not allocate: only the links between nodes are flipped.
+    pub fn reverse(&mut self) {
+        let mut reversed = None;
+        let mut cursor = self.head.take();
+
+        while let Some(mut node) = cursor {
+            cursor = std::mem::replace(&mut node.next, reversed);
+            reversed = Some(node);
+        }
+
+        self.head = reversed;
+    }
+
+    /// Returns a reference to the `n`th element from the top of the stack, or [`None`] if `n` is
+    /// out of bounds.
+    pub fn get(&self, n: usize) -> Option<&T> {
+        self.iter().nth(n)
+    }
+
+    /// Returns a mutable reference to the `n`th element from the top of the stack, or [`None`]
+    /// if `n` is out of bounds.
+    pub fn get_mut(&mut self, n: usize) -> Option<&mut T> {
+        self.iter_mut().nth(n)
+    }
+
+    /// Inserts `elem` so that it ends up at index `n`, counting from the top of the stack.
+    /// Inserting at index 0 is the same as a [`push`](Self::push).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the stack.
+    pub fn insert(&mut self, n: usize, elem: T) {
+        assert!(
+            n <= self.size,
+            "Cannot insert at index {n}, stack only has {} elements",
+            self.size
+        );
+
+        let link = link_at(&mut self.head, n);
+        let next = link.take();
+        *link = Some(self.pool.alloc(elem, next, &self.alloc));
+        self.size += 1;
+    }
+
+    /// Removes and returns the `n`th element from the top of the stack, or [`None`] if `n` is
+    /// out of bounds. Removing at index 0 is the same as a [`pop`](Self::pop).
+    pub fn remove(&mut self, n: usize) -> Option<T> {
+        if n >= self.size {
+            return None;
+        }
+
+        let link = link_at(&mut self.head, n);
+        link.take().map(|mut node| {
+            *link = node.next.take();
+            self.size -= 1;
+            self.pool.release(node)
+        })
+    }
+
+    /// Swaps the `i`th and `j`th elements from the top of the stack.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(
+            i < self.size && j < self.size,
+            "Cannot swap indices {i} and {j}, stack only has {} elements",
+            self.size
+        );
+
+        let (i, j) = (i.min(j), i.max(j));
+        if i == j {
+            return;
+        }
+
+        // The unwraps are safe since we checked both indices are in bounds above
+        let first = unsafe { link_at(&mut self.head, i).as_deref_mut().unwrap_unchecked() };
+        let mut second = unsafe { first.next.as_deref_mut().unwrap_unchecked() };
+        for _ in i + 1..j {
+            second = unsafe { second.next.as_deref_mut().unwrap_unchecked() };
+        }
+
+        std::mem::swap(&mut first.elem, &mut second.elem);
+    }
+
+    /// Splits the stack in two at index `n`. The stack keeps its top `n` elements and the rest
+    /// are returned in a new stack, in the same order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the stack.
+    pub fn split_off(&mut self, n: usize) -> Self {
+        assert!(
+            n <= self.size,
+            "Cannot split off at index {n}, stack only has {} elements",
+            self.size
+        );
+
+        let head = link_at(&mut self.head, n).take();
+        let size = self.size - n;
+        self.size = n;
+
+        Self {
+            head,
+            size,
+            pool: Pool::new(self.pool.capacity),
+            journal: Vec::new(),
+            alloc: self.alloc.clone(),
+        }
+    }
+
+    /// Moves all the elements of `other` on top of the stack, leaving `other` empty. The
+    /// elements keep their order, so the top of `other` becomes the new top of the stack.
+    ///
+    /// This runs in O(n) time, where n is the length of `other`.
+    pub fn append(&mut self, other: &mut Self) {
+        if other.is_empty() {
+            return;
+        }
+
+        let size = other.size;
+        let tail = link_at(&mut other.head, size);
+        *tail = self.head.take();
+        self.head = other.head.take();
+        self.size += size;
+        other.size = 0;
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, see [`retain_mut`](Self::retain_mut).
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_mut(|elem| f(elem));
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, visiting them from top to bottom.
+    /// Rejected nodes are unlinked in place.
+    ///
+    /// If `f` panics, the elements visited so far have been filtered and the rest are kept.
+    pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        let mut cursor = &mut self.head;
+
+        while let Some(node) = cursor.as_mut() {
+            if f(&mut node.elem) {
+                // The unwrap is safe since we just checked the link above
+                cursor = unsafe { &mut cursor.as_mut().unwrap_unchecked().next };
+            } else {
+                let mut node = unsafe { cursor.take().unwrap_unchecked() };
+                *cursor = node.next.take();
+                self.size -= 1;
+                self.pool.release(node);
+            }
+        }
+    }
+
+    /// Returns an iterator which lazily removes and yields the elements for which `filter`
+    /// returns `true`, from top to bottom.
+    ///
+    /// Elements which are not yielded, either because `filter` returned `false` or because the
+    /// iterator was dropped early, stay in the stack. If `filter` panics, the iterator stops and
+    /// every element it has not yielded stays in the stack.
+    pub fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, filter: F) -> ExtractIf<'_, T, F, A> {
+        ExtractIf {
+            cursor: Some(&mut self.head),
+            size: &mut self.size,
+            pool: &mut self.pool,
+            filter,
+        }
+    }
+
+    /// Removes and returns the topmost element for which `pred` returns `true`.
+    pub fn remove_first<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> Option<T> {
+        self.extract_if(|elem| pred(elem)).next()
+    }
+
+    /// Removes consecutive repeated elements, see [`dedup_by`](Self::dedup_by).
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+
+    /// Removes consecutive elements which map to the same key, see [`dedup_by`](Self::dedup_by).
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes consecutive elements for which `same_bucket` returns `true`, keeping the first one
+    /// from the top.
+    ///
+    /// `same_bucket(a, b)` is passed each element `a` along with the last element `b` kept above
+    /// it, just like [`Vec::dedup_by`]. If it panics, the elements visited so far have been
+    /// deduplicated and the rest are kept.
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same_bucket: F) {
+        let Some(mut kept) = self.head.as_deref_mut() else {
+            return;
+        };
+
+        while let Some(node) = kept.next.as_deref_mut() {
+            if same_bucket(&mut node.elem, &mut kept.elem) {
+                // The unwrap is safe since we just checked the link above
+                let mut node = unsafe { kept.next.take().unwrap_unchecked() };
+                kept.next = node.next.take();
+                self.size -= 1;
+                self.pool.release(node);
+            } else {
+                kept = unsafe { kept.next.as_deref_mut().unwrap_unchecked() };
+            }
+        }
+    }
+
+    /// Sorts the stack from top to bottom, see [`sort_by`](Self::sort_by).
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    /// Sorts the stack from top to bottom by the key extracted with `f`, see
+    /// [`sort_by`](Self::sort_by).
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    /// Sorts the stack from top to bottom with a stable, bottom-up merge sort.
+    ///
+    /// This runs in O(n log n) time and O(1) extra space: elements are never moved, only the
+    /// nodes holding them are relinked. If `compare` panics, the stack is left holding all of its
+    /// elements in an unspecified order.
+    pub fn sort_by<F: FnMut(&T, &T) -> std::cmp::Ordering>(&mut self, mut compare: F) {
+        if self.size < 2 {
+            return;
+        }
+
+        let size = self.size;
+        let mut sort = MergeSort {
+            unsorted: self.head.take(),
+            sorted: None,
+            left: None,
+            right: None,
+            stack: self,
+        };
+
+        // Each pass merges pairs of sorted runs of length `width` into runs of length `2 * width`
+        let mut width = 1;
+        while width < size {
+            let mut tail = &mut sort.sorted;
+
+            while sort.unsorted.is_some() {
+                sort.left = sort.unsorted.take();
+                sort.right = split_after(&mut sort.left, width);
+                sort.unsorted = split_after(&mut sort.right, width);
+
+                loop {
+                    // Ties go to the left run, which is what keeps the sort stable
+                    let take_right = match (&sort.left, &sort.right) {
+                        (Some(left), Some(right)) => {
+                            compare(&right.elem, &left.elem) == std::cmp::Ordering::Less
+                        }
+                        (Some(_), None) => false,
+                        (None, Some(_)) => true,
+                        (None, None) => break,
+                    };
+
+                    let run = if take_right {
+                        &mut sort.right
+                    } else {
+                        &mut sort.left
+                    };
+
+                    // The unwrap is safe since we checked the run was not empty above
+                    let mut node = unsafe { run.take().unwrap_unchecked() };
+                    *run = node.next.take();
+                    tail = &mut tail.insert(node).next;
+                }
+            }
+
+            sort.unsorted = sort.sorted.take();
+            width *= 2;
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         Iter {
             node: self.head.as_deref(),
@@ -135,18 +645,108 @@ impl<T> LinkedStack<T> {
     }
 }
 
-impl<T> IntoIterator for LinkedStack<T> {
+/// Holds on to every node of a [`LinkedStack`] while it is being sorted. If the comparison
+/// function panics, dropping this links all the nodes back into the stack so that no element is
+/// lost.
+struct MergeSort<'a, T, A: Allocator> {
+    stack: &'a mut LinkedStack<T, A>,
+    /// Output of the current pass.
+    sorted: Link<T, A>,
+    /// The two runs currently being merged.
+    left: Link<T, A>,
+    right: Link<T, A>,
+    /// Input of the current pass which has not been merged yet.
+    unsorted: Link<T, A>,
+}
+
+impl<T, A: Allocator> Drop for MergeSort<'_, T, A> {
+    fn drop(&mut self) {
+        let mut tail = &mut self.stack.head;
+
+        for list in [
+            self.sorted.take(),
+            self.left.take(),
+            self.right.take(),
+            self.unsorted.take(),
+        ] {
+            *tail = list;
+            while let Some(node) = tail {
+                tail = &mut node.next;
+            }
+        }
+    }
+}
+
+/// Cuts `list` after its first `n` nodes, returning the rest.
+fn split_after<T, A: Allocator>(list: &mut Link<T, A>, n: usize) -> Link<T, A> {
+    let mut cursor = list;
+    for _ in 0..n {
+        match cursor {
+            Some(node) => cursor = &mut node.next,
+            None => return None,
+        }
+    }
+    cursor.take()
+}
+
+/// Returns the link holding the `n`th node of `list`, which is its last link if `n` is the
+/// length of the list. `n` must not be greater than that.
+fn link_at<T, A: Allocator>(list: &mut Link<T, A>, n: usize) -> &mut Link<T, A> {
+    let mut link = list;
+    for _ in 0..n {
+        // The unwrap is safe since callers check `n` against the stack size
+        link = unsafe { &mut link.as_mut().unwrap_unchecked().next };
+    }
+    link
+}
+
+/// Iterator returned by [`LinkedStack::extract_if`].
+///
+/// `cursor` points to the link holding the next node to check, and is taken out while `filter`
+/// runs so that a panic leaves the iterator exhausted.
+pub struct ExtractIf<'a, T, F, A: Allocator = Global> {
+    cursor: Option<&'a mut Link<T, A>>,
+    size: &'a mut usize,
+    pool: &'a mut Pool<T, A>,
+    filter: F,
+}
+
+impl<T, F: FnMut(&mut T) -> bool, A: Allocator> Iterator for ExtractIf<'_, T, F, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(cursor) = self.cursor.take() {
+            let node = cursor.as_mut()?;
+
+            if (self.filter)(&mut node.elem) {
+                // The unwrap is safe since we just checked the link above
+                let mut node = unsafe { cursor.take().unwrap_unchecked() };
+                *cursor = node.next.take();
+                *self.size -= 1;
+                self.cursor = Some(cursor);
+                return Some(self.pool.release(node));
+            } else {
+                self.cursor = cursor.as_mut().map(|node| &mut node.next);
+            }
+        }
+
+        None
+    }
+}
+
+impl<T, A: Allocator + Clone> IntoIterator for LinkedStack<T, A> {
     type Item = T;
-    type IntoIter = IntoIter<T>;
+    type IntoIter = IntoIter<T, A>;
 
-    fn into_iter(self) -> Self::IntoIter {
+    fn into_iter(mut self) -> Self::IntoIter {
+        self.release_held();
         IntoIter(self)
     }
 }
 
-pub struct IntoIter<T>(LinkedStack<T>);
+pub struct IntoIter<T, A: Allocator = Global>(LinkedStack<T, A>);
 
-impl<T> Iterator for IntoIter<T> {
+impl<T, A: Allocator + Clone> Iterator for IntoIter<T, A> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -154,11 +754,11 @@ impl<T> Iterator for IntoIter<T> {
     }
 }
 
-pub struct Iter<'a, T> {
-    node: Option<&'a Node<T>>,
+pub struct Iter<'a, T, A: Allocator = Global> {
+    node: Option<&'a Node<T, A>>,
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, A: Allocator> Iterator for Iter<'a, T, A> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -169,11 +769,11 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
-pub struct IterMut<'a, T> {
-    node: Option<&'a mut Node<T>>,
+pub struct IterMut<'a, T, A: Allocator = Global> {
+    node: Option<&'a mut Node<T, A>>,
 }
 
-impl<'a, T> Iterator for IterMut<'a, T> {
+impl<'a, T, A: Allocator> Iterator for IterMut<'a, T, A> {
     type Item = &'a mut T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -184,19 +784,145 @@ impl<'a, T> Iterator for IterMut<'a, T> {
     }
 }
 
+/// A point in the history of a [`LinkedStack`] which it can be rolled back to, returned by
+/// [`LinkedStack::checkpoint`].
+///
+/// The checkpoint holds on to the stack until it is resolved with [`commit`](Self::commit) or
+/// [`rollback`](Self::rollback), and rolls back if it is dropped first. Leaking it with
+/// [`std::mem::forget`] keeps the stack in its current state, but popped elements are only dropped
+/// on the next call to [`LinkedStack::checkpoint`] or when the stack is dropped.
+///
+/// Checkpoints can be nested with [`checkpoint`](Self::checkpoint), in which case the borrow
+/// checker makes sure they are resolved in the reverse order they were taken. The stack can be
+/// read through a checkpoint, but in-place changes are not possible since they would not be
+/// undone.
+#[must_use = "dropping a checkpoint rolls it back"]
+pub struct Checkpoint<'a, T, A: Allocator + Clone = Global> {
+    stack: &'a mut LinkedStack<T, A>,
+    journal_len: usize,
+    outermost: bool,
+}
+
+impl<T: std::fmt::Debug, A: Allocator + Clone> std::fmt::Debug for Checkpoint<'_, T, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Checkpoint").field(&self.stack).finish()
+    }
+}
+
+impl<T, A: Allocator + Clone> std::ops::Deref for Checkpoint<'_, T, A> {
+    type Target = LinkedStack<T, A>;
+
+    fn deref(&self) -> &Self::Target {
+        self.stack
+    }
+}
+
+impl<T, A: Allocator + Clone> Drop for Checkpoint<'_, T, A> {
+    fn drop(&mut self) {
+        self.undo();
+    }
+}
+
+impl<T, A: Allocator + Clone> Checkpoint<'_, T, A> {
+    /// Pushes `elem` onto the stack, recording it so that it is dropped on rollback.
+    pub fn push(&mut self, elem: T) {
+        self.stack.push(elem);
+        self.stack.journal.push(Change::Push);
+    }
+
+    /// Removes the top element of the stack and returns a reference to it.
+    ///
+    /// The element is not dropped: it is held until the outermost checkpoint has been committed,
+    /// so that a rollback can put it back in place.
+    pub fn pop(&mut self) -> Option<&T> {
+        let stack = &mut *self.stack;
+        let mut node = stack.head.take()?;
+        stack.head = node.next.take();
+        stack.size -= 1;
+        stack.journal.push(Change::Pop(node));
+
+        let Some(Change::Pop(node)) = stack.journal.last() else {
+            unreachable!("The node was just pushed to the journal")
+        };
+        Some(&node.elem)
+    }
+
+    /// Takes a nested checkpoint, which can be rolled back on its own while this one is pending.
+    pub fn checkpoint(&mut self) -> Checkpoint<'_, T, A> {
+        Checkpoint {
+            journal_len: self.stack.journal.len(),
+            outermost: false,
+            stack: self.stack,
+        }
+    }
+
+    /// Keeps the changes made since the checkpoint was taken.
+    ///
+    /// If this is the outermost checkpoint, elements popped since then are dropped. Otherwise
+    /// they are held until the enclosing checkpoint is committed, since it may still be rolled
+    /// back.
+    pub fn commit(mut self) {
+        if self.outermost {
+            self.stack.release_held();
+        }
+        // Nothing is left for the drop to undo
+        self.journal_len = self.stack.journal.len();
+    }
+
+    /// Restores the stack to the state it was in when the checkpoint was taken: elements pushed
+    /// since then are dropped, and elements popped since then are put back.
+    pub fn rollback(mut self) {
+        self.undo();
+    }
+
+    fn undo(&mut self) {
+        let stack = &mut *self.stack;
+
+        while stack.journal.len() > self.journal_len {
+            let change = stack
+                .journal
+                .pop()
+                .expect("The journal is longer than when the checkpoint was taken");
+
+            match change {
+                Change::Push => {
+                    let mut node = stack.head.take().expect(
+                        "The stack is borrowed by the checkpoint, so every recorded push is \
+                         still on the stack",
+                    );
+                    stack.head = node.next.take();
+                    stack.size -= 1;
+                    stack.pool.release(node);
+                }
+                Change::Pop(mut node) => {
+                    node.next = stack.head.take();
+                    stack.head = Some(node);
+                    stack.size += 1;
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod est {
-    use dsa_util::DropCounter;
+    use dsa_util::{CountingAllocator, DropCounter, TrackingAllocator};
 
     use super::*;
 
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
     #[test]
     fn linked_stack_new() {
         assert_eq!(
             LinkedStack::<()>::new(),
             LinkedStack {
                 head: None,
-                size: 0
+                size: 0,
+                pool: Pool::new(0),
+                journal: Vec::new(),
+                alloc: Global
             }
         );
     }
@@ -345,4 +1071,953 @@ mod est {
         }
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn linked_stack_sort() {
+        let mut linked_stack = LinkedStack::new();
+        for n in [5, 3, 8, 0, 9, 1, 7, 2, 6, 4] {
+            linked_stack.push(n);
+        }
+
+        linked_stack.sort();
+        assert_eq!(linked_stack.len(), 10);
+        for n in 0..10 {
+            assert_eq!(linked_stack.pop(), Some(n));
+        }
+        assert_eq!(linked_stack.pop(), None);
+    }
+
+    #[test]
+    fn linked_stack_sort_edge_cases() {
+        let mut linked_stack = LinkedStack::<i32>::new();
+        linked_stack.sort();
+        assert!(linked_stack.is_empty());
+
+        linked_stack.push(0);
+        linked_stack.sort();
+        assert_eq!(linked_stack.len(), 1);
+        assert_eq!(linked_stack.peek(), Some(&0));
+
+        // Sizes which are not a power of two leave an odd run at the end of each pass
+        for size in 2..40 {
+            let mut linked_stack = LinkedStack::new();
+            for n in 0..size {
+                linked_stack.push((size + size / 2 - 1 - n) % size);
+            }
+
+            linked_stack.sort();
+            assert_eq!(linked_stack.len(), size);
+            for n in 0..size {
+                assert_eq!(linked_stack.pop(), Some(n));
+            }
+        }
+    }
+
+    #[test]
+    fn linked_stack_sort_by_stable() {
+        let mut linked_stack = LinkedStack::new();
+        for n in (0..20).rev() {
+            linked_stack.push((n % 3, n));
+        }
+
+        linked_stack.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // Elements with equal keys keep their relative order
+        let mut expected = (0..20).map(|n| (n % 3, n)).collect::<Vec<_>>();
+        expected.sort_by_key(|(k, _)| *k);
+        for elem in expected {
+            assert_eq!(linked_stack.pop(), Some(elem));
+        }
+    }
+
+    #[test]
+    fn linked_stack_sort_by_key() {
+        let mut linked_stack = LinkedStack::new();
+        for n in 0..10 {
+            linked_stack.push(n);
+        }
+
+        linked_stack.sort_by_key(|n| std::cmp::Reverse(*n));
+        for n in (0..10).rev() {
+            assert_eq!(linked_stack.pop(), Some(n));
+        }
+    }
+
+    #[test]
+    fn linked_stack_sort_long() {
+        let mut linked_stack = LinkedStack::new();
+        for n in 0..100_000 {
+            linked_stack.push(n);
+        }
+
+        linked_stack.sort();
+        assert_eq!(linked_stack.len(), 100_000);
+        for (i, n) in linked_stack.iter().enumerate() {
+            assert_eq!(i, *n);
+        }
+    }
+
+    #[test]
+    fn linked_stack_sort_drop() {
+        let mut linked_stack = LinkedStack::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..10 {
+            linked_stack.push((n, DropCounter::new(&rc, vec![n])));
+        }
+
+        linked_stack.sort_by_key(|(n, _)| *n);
+        assert_eq!(rc.get(), 0);
+        assert_eq!(linked_stack.len(), 10);
+
+        drop(linked_stack);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_stack_sort_panic() {
+        for panic_at in [0, 1, 5, 10, 15] {
+            let mut linked_stack = LinkedStack::new();
+            let rc = std::rc::Rc::default();
+
+            for n in [5, 3, 8, 0, 9, 1, 7, 2, 6, 4] {
+                linked_stack.push((n, DropCounter::new(&rc, vec![n])));
+            }
+
+            let mut comparisons = 0;
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                linked_stack.sort_by(|a, b| {
+                    if comparisons == panic_at {
+                        panic!("Comparator panicked");
+                    }
+                    comparisons += 1;
+                    a.0.cmp(&b.0)
+                })
+            }));
+            assert!(res.is_err());
+
+            // No element should have been dropped or duplicated
+            assert_eq!(rc.get(), 0);
+            assert_eq!(linked_stack.len(), 10);
+            let mut elems = linked_stack.iter().map(|(n, _)| *n).collect::<Vec<_>>();
+            elems.sort();
+            assert_eq!(elems, (0..10).collect::<Vec<_>>());
+
+            // The stack should still be usable
+            linked_stack.sort_by_key(|(n, _)| *n);
+            assert_eq!(linked_stack.peek().map(|(n, _)| *n), Some(0));
+
+            drop(linked_stack);
+            assert_eq!(rc.get(), 10);
+        }
+    }
+
+    #[test]
+    fn linked_stack_retain() {
+        let mut linked_stack = LinkedStack::new();
+        for n in 0..10 {
+            linked_stack.push(n);
+        }
+
+        linked_stack.retain(|n| n % 2 == 0);
+        assert_eq!(linked_stack.len(), 5);
+        for n in (0..10).rev().step_by(2).map(|n| n - 1) {
+            assert_eq!(linked_stack.pop(), Some(n));
+        }
+        assert_eq!(linked_stack.pop(), None);
+
+        for n in 0..10 {
+            linked_stack.push(n);
+        }
+        linked_stack.retain(|_| false);
+        assert!(linked_stack.is_empty());
+        assert!(linked_stack.head.is_none());
+
+        linked_stack.retain(|_| true);
+        assert!(linked_stack.is_empty());
+    }
+
+    #[test]
+    fn linked_stack_retain_mut() {
+        let mut linked_stack = LinkedStack::new();
+        for n in 0..10 {
+            linked_stack.push(n);
+        }
+
+        linked_stack.retain_mut(|n| {
+            *n *= 10;
+            *n < 50
+        });
+        assert_eq!(linked_stack.len(), 5);
+        for n in (0..5).rev() {
+            assert_eq!(linked_stack.pop(), Some(n * 10));
+        }
+    }
+
+    #[test]
+    fn linked_stack_retain_panic() {
+        let mut linked_stack = LinkedStack::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..10 {
+            linked_stack.push((n, DropCounter::new(&rc, vec![n])));
+        }
+
+        // Visits 9, 8, 7, 6 and panics on 5
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            linked_stack.retain(|(n, _)| {
+                assert_ne!(*n, 5);
+                n % 2 == 0
+            })
+        }));
+        assert!(res.is_err());
+
+        assert_eq!(rc.get(), 2);
+        assert_eq!(linked_stack.len(), 8);
+        assert_eq!(
+            linked_stack.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            [8, 6, 5, 4, 3, 2, 1, 0]
+        );
+
+        drop(linked_stack);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_stack_extract_if() {
+        let mut linked_stack = LinkedStack::new();
+        for n in 0..10 {
+            linked_stack.push(n);
+        }
+
+        let mut iter = linked_stack.extract_if(|n| *n % 3 == 0);
+        assert_eq!(iter.next(), Some(9));
+        assert_eq!(iter.next(), Some(6));
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(linked_stack.len(), 6);
+        assert_eq!(
+            linked_stack.iter().copied().collect::<Vec<_>>(),
+            [8, 7, 5, 4, 2, 1]
+        );
+    }
+
+    #[test]
+    fn linked_stack_extract_if_lazy() {
+        let mut linked_stack = LinkedStack::new();
+        for n in 0..10 {
+            linked_stack.push(n);
+        }
+
+        // Dropping the iterator early leaves the remaining elements in place
+        {
+            let mut iter = linked_stack.extract_if(|n| *n % 2 == 1);
+            assert_eq!(iter.next(), Some(9));
+            assert_eq!(iter.next(), Some(7));
+        }
+
+        assert_eq!(linked_stack.len(), 8);
+        assert_eq!(
+            linked_stack.iter().copied().collect::<Vec<_>>(),
+            [8, 6, 5, 4, 3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn linked_stack_extract_if_panic() {
+        let mut linked_stack = LinkedStack::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..10 {
+            linked_stack.push((n, DropCounter::new(&rc, vec![n])));
+        }
+
+        let mut extracted = Vec::new();
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            for elem in linked_stack.extract_if(|(n, _)| {
+                assert_ne!(*n, 4);
+                *n % 2 == 1
+            }) {
+                extracted.push(elem);
+            }
+        }));
+        assert!(res.is_err());
+
+        assert_eq!(rc.get(), 0);
+        assert_eq!(extracted.len(), 3);
+        assert_eq!(linked_stack.len(), 7);
+        assert_eq!(
+            linked_stack.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            [8, 6, 4, 3, 2, 1, 0]
+        );
+
+        drop(extracted);
+        drop(linked_stack);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn linked_stack_remove_first() {
+        let mut linked_stack = LinkedStack::new();
+        for n in 0..10 {
+            linked_stack.push(n);
+        }
+
+        assert_eq!(linked_stack.remove_first(|n| *n < 5), Some(4));
+        assert_eq!(linked_stack.remove_first(|n| *n == 9), Some(9));
+        assert_eq!(linked_stack.remove_first(|n| *n == 0), Some(0));
+        assert_eq!(linked_stack.remove_first(|n| *n > 10), None);
+
+        assert_eq!(linked_stack.len(), 7);
+        assert_eq!(
+            linked_stack.iter().copied().collect::<Vec<_>>(),
+            [8, 7, 6, 5, 3, 2, 1]
+        );
+    }
+
+    #[test]
+    fn linked_stack_dedup() {
+        let mut linked_stack = LinkedStack::new();
+        for n in [0, 0, 1, 1, 1, 2, 3, 3, 0, 0] {
+            linked_stack.push(n);
+        }
+
+        linked_stack.dedup();
+        assert_eq!(linked_stack.len(), 5);
+        assert_eq!(
+            linked_stack.iter().copied().collect::<Vec<_>>(),
+            [0, 3, 2, 1, 0]
+        );
+
+        let mut linked_stack = LinkedStack::<i32>::new();
+        linked_stack.dedup();
+        linked_stack.push(0);
+        linked_stack.dedup();
+        assert_eq!(linked_stack.len(), 1);
+    }
+
+    #[test]
+    fn linked_stack_dedup_by_key() {
+        let mut linked_stack = LinkedStack::new();
+        for n in 0..10 {
+            linked_stack.push(n);
+        }
+
+        linked_stack.dedup_by_key(|n| *n / 3);
+        assert_eq!(linked_stack.len(), 4);
+        assert_eq!(
+            linked_stack.iter().copied().collect::<Vec<_>>(),
+            [9, 8, 5, 2]
+        );
+    }
+
+    #[test]
+    fn linked_stack_dedup_by_panic() {
+        let mut linked_stack = LinkedStack::new();
+        let rc = std::rc::Rc::default();
+
+        for n in [0, 0, 1, 1, 2, 2] {
+            linked_stack.push((n, DropCounter::new(&rc, vec![n])));
+        }
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            linked_stack.dedup_by(|a, b| {
+                assert_ne!(a.0, 0);
+                a.0 == b.0
+            })
+        }));
+        assert!(res.is_err());
+
+        assert_eq!(rc.get(), 2);
+        assert_eq!(linked_stack.len(), 4);
+        assert_eq!(
+            linked_stack.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            [2, 1, 0, 0]
+        );
+
+        drop(linked_stack);
+        assert_eq!(rc.get(), 6);
+    }
+
+    fn stack_of(elems: impl IntoIterator<Item = i32>) -> LinkedStack<i32> {
+        let mut linked_stack = LinkedStack::new();
+        for n in elems {
+            linked_stack.push(n);
+        }
+        linked_stack
+    }
+
+    fn to_vec(linked_stack: &LinkedStack<i32>) -> Vec<i32> {
+        linked_stack.iter().copied().collect()
+    }
+
+    #[test]
+    fn linked_stack_get() {
+        let mut linked_stack = stack_of(0..10);
+
+        for n in 0..10 {
+            assert_eq!(linked_stack.get(n), Some(&(9 - n as i32)));
+        }
+        assert_eq!(linked_stack.get(10), None);
+
+        *linked_stack.get_mut(3).unwrap() = 42;
+        assert_eq!(linked_stack.get(3), Some(&42));
+        assert_eq!(linked_stack.get_mut(10), None);
+        assert_eq!(LinkedStack::<i32>::new().get(0), None);
+    }
+
+    #[test]
+    fn linked_stack_insert() {
+        let mut linked_stack = LinkedStack::new();
+
+        linked_stack.insert(0, 1);
+        linked_stack.insert(1, 3);
+        linked_stack.insert(0, 0);
+        linked_stack.insert(2, 2);
+        linked_stack.insert(4, 4);
+
+        assert_eq!(linked_stack.len(), 5);
+        assert_eq!(to_vec(&linked_stack), [0, 1, 2, 3, 4]);
+        assert_eq!(linked_stack.pop(), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot insert at index 3, stack only has 2 elements")]
+    fn linked_stack_insert_out_of_bounds() {
+        let mut linked_stack = stack_of(0..2);
+        linked_stack.insert(3, 0);
+    }
+
+    #[test]
+    fn linked_stack_remove() {
+        let mut linked_stack = stack_of(0..10);
+
+        assert_eq!(linked_stack.remove(10), None);
+        assert_eq!(linked_stack.remove(9), Some(0));
+        assert_eq!(linked_stack.remove(3), Some(6));
+        assert_eq!(linked_stack.remove(0), Some(9));
+
+        assert_eq!(linked_stack.len(), 7);
+        assert_eq!(to_vec(&linked_stack), [8, 7, 5, 4, 3, 2, 1]);
+
+        let rc = std::rc::Rc::default();
+        let mut linked_stack = LinkedStack::new();
+        for n in 0..3 {
+            linked_stack.push(DropCounter::new(&rc, vec![n]));
+        }
+
+        drop(linked_stack.remove(1));
+        assert_eq!(rc.get(), 1);
+        drop(linked_stack);
+        assert_eq!(rc.get(), 3);
+    }
+
+    #[test]
+    fn linked_stack_swap() {
+        let mut linked_stack = stack_of(0..5);
+
+        linked_stack.swap(0, 4);
+        assert_eq!(to_vec(&linked_stack), [0, 3, 2, 1, 4]);
+
+        linked_stack.swap(3, 1);
+        assert_eq!(to_vec(&linked_stack), [0, 1, 2, 3, 4]);
+
+        linked_stack.swap(2, 2);
+        linked_stack.swap(1, 2);
+        assert_eq!(to_vec(&linked_stack), [0, 2, 1, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot swap indices 0 and 5, stack only has 5 elements")]
+    fn linked_stack_swap_out_of_bounds() {
+        let mut linked_stack = stack_of(0..5);
+        linked_stack.swap(0, 5);
+    }
+
+    #[test]
+    fn linked_stack_split_off() {
+        let mut linked_stack = stack_of(0..10);
+
+        let bottom = linked_stack.split_off(4);
+        assert_eq!(linked_stack.len(), 4);
+        assert_eq!(bottom.len(), 6);
+        assert_eq!(to_vec(&linked_stack), [9, 8, 7, 6]);
+        assert_eq!(to_vec(&bottom), [5, 4, 3, 2, 1, 0]);
+
+        let empty = linked_stack.split_off(4);
+        assert!(empty.is_empty());
+        assert_eq!(linked_stack.len(), 4);
+
+        let all = linked_stack.split_off(0);
+        assert!(linked_stack.is_empty());
+        assert_eq!(to_vec(&all), [9, 8, 7, 6]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot split off at index 11, stack only has 10 elements")]
+    fn linked_stack_split_off_out_of_bounds() {
+        let mut linked_stack = stack_of(0..10);
+        linked_stack.split_off(11);
+    }
+
+    #[test]
+    fn linked_stack_append() {
+        let mut linked_stack = stack_of(0..3);
+        let mut other = stack_of(3..6);
+
+        linked_stack.append(&mut other);
+        assert!(other.is_empty());
+        assert!(other.head.is_none());
+        assert_eq!(linked_stack.len(), 6);
+        assert_eq!(to_vec(&linked_stack), [5, 4, 3, 2, 1, 0]);
+
+        linked_stack.append(&mut other);
+        assert_eq!(linked_stack.len(), 6);
+
+        let mut empty = LinkedStack::new();
+        empty.append(&mut linked_stack);
+        assert!(linked_stack.is_empty());
+        assert_eq!(to_vec(&empty), [5, 4, 3, 2, 1, 0]);
+
+        empty.push(6);
+        assert_eq!(empty.pop(), Some(6));
+        assert_eq!(empty.split_off(5).pop(), Some(0));
+    }
+
+    #[test]
+    fn linked_stack_from_iter() {
+        let linked_stack = (0..10).collect::<LinkedStack<_>>();
+        assert_eq!(linked_stack.len(), 10);
+        assert_eq!(linked_stack.peek(), Some(&9));
+        assert_eq!(to_vec(&linked_stack), [9, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+
+        let linked_stack = LinkedStack::from_iter_rev(0..10);
+        assert_eq!(linked_stack.len(), 10);
+        assert_eq!(linked_stack.peek(), Some(&0));
+        assert_eq!(to_vec(&linked_stack), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(
+            linked_stack.into_iter().collect::<Vec<_>>(),
+            (0..10).collect::<Vec<_>>()
+        );
+
+        assert!(LinkedStack::<i32>::from_iter_rev([]).is_empty());
+        assert!(
+            std::iter::empty::<i32>()
+                .collect::<LinkedStack<_>>()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn linked_stack_extend() {
+        let mut linked_stack = LinkedStack::from_iter_rev([1, 0]);
+
+        linked_stack.extend(2..4);
+        linked_stack.extend(&[4, 5]);
+
+        assert_eq!(linked_stack.len(), 6);
+        assert_eq!(to_vec(&linked_stack), [5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn linked_stack_reverse() {
+        let mut linked_stack = (0..10).collect::<LinkedStack<_>>();
+
+        linked_stack.reverse();
+        assert_eq!(linked_stack.len(), 10);
+        assert_eq!(to_vec(&linked_stack), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        linked_stack.reverse();
+        assert_eq!(to_vec(&linked_stack), [9, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+
+        let mut linked_stack = LinkedStack::<i32>::new();
+        linked_stack.reverse();
+        assert!(linked_stack.is_empty());
+
+        linked_stack.push(0);
+        linked_stack.reverse();
+        assert_eq!(to_vec(&linked_stack), [0]);
+    }
+
+    #[test]
+    fn linked_stack_pool_reuse() {
+        let mut linked_stack = LinkedStack::with_pool_capacity(4);
+        assert_eq!(linked_stack.pool_capacity(), 4);
+
+        for n in 0..4 {
+            linked_stack.push(n);
+        }
+        for n in (0..4).rev() {
+            assert_eq!(linked_stack.pop(), Some(n));
+        }
+        assert_eq!(linked_stack.pool_len(), 4);
+
+        // Pushing and popping within the pool capacity never allocates
+        let allocations = CountingAllocator::allocations();
+        for _ in 0..1000 {
+            for n in 0..4 {
+                linked_stack.push(n);
+            }
+            for n in (0..4).rev() {
+                assert_eq!(linked_stack.pop(), Some(n));
+            }
+        }
+        assert_eq!(CountingAllocator::allocations(), allocations);
+
+        // Only the nodes which do not fit in the pool are allocated
+        for n in 0..6 {
+            linked_stack.push(n);
+        }
+        assert_eq!(CountingAllocator::allocations(), allocations + 2);
+        assert_eq!(linked_stack.pool_len(), 0);
+        assert_eq!(to_vec(&linked_stack), [5, 4, 3, 2, 1, 0]);
+
+        for n in (0..6).rev() {
+            assert_eq!(linked_stack.pop(), Some(n));
+        }
+        assert_eq!(linked_stack.pool_len(), 4);
+    }
+
+    #[test]
+    fn linked_stack_pool_disabled() {
+        let mut linked_stack = LinkedStack::new();
+        assert_eq!(linked_stack.pool_capacity(), 0);
+
+        let allocations = CountingAllocator::allocations();
+        for n in 0..10 {
+            linked_stack.push(n);
+            linked_stack.pop();
+        }
+        assert_eq!(CountingAllocator::allocations(), allocations + 10);
+        assert_eq!(linked_stack.pool_len(), 0);
+    }
+
+    #[test]
+    fn linked_stack_pool_positional() {
+        let mut linked_stack = LinkedStack::with_pool_capacity(8);
+        linked_stack.extend([0, 0, 1, 2, 2, 3]);
+
+        assert_eq!(linked_stack.remove(3), Some(1));
+        linked_stack.dedup();
+        linked_stack.retain(|n| *n != 3);
+        assert_eq!(linked_stack.pool_len(), 4);
+        assert_eq!(to_vec(&linked_stack), [2, 0]);
+
+        let allocations = CountingAllocator::allocations();
+        linked_stack.insert(1, 1);
+        linked_stack.push(3);
+        assert_eq!(CountingAllocator::allocations(), allocations);
+        assert_eq!(linked_stack.pool_len(), 2);
+        assert_eq!(to_vec(&linked_stack), [3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn linked_stack_pool_shrink() {
+        let mut linked_stack = LinkedStack::with_pool_capacity(8);
+        linked_stack.extend(0..8);
+        while linked_stack.pop().is_some() {}
+        assert_eq!(linked_stack.pool_len(), 8);
+
+        linked_stack.set_pool_capacity(3);
+        assert_eq!(linked_stack.pool_len(), 3);
+        assert_eq!(linked_stack.pool_capacity(), 3);
+
+        linked_stack.shrink_pool();
+        assert_eq!(linked_stack.pool_len(), 0);
+        assert_eq!(linked_stack.pool_capacity(), 3);
+
+        let allocations = CountingAllocator::allocations();
+        linked_stack.push(0);
+        assert_eq!(CountingAllocator::allocations(), allocations + 1);
+    }
+
+    #[test]
+    fn linked_stack_pool_drop() {
+        let rc = std::rc::Rc::default();
+        let mut linked_stack = LinkedStack::with_pool_capacity(4);
+
+        for n in 0..8 {
+            linked_stack.push(DropCounter::new(&rc, vec![n]));
+        }
+        for _ in 0..6 {
+            drop(linked_stack.pop());
+        }
+        assert_eq!(rc.get(), 6);
+        assert_eq!(linked_stack.pool_len(), 4);
+
+        // Pooled nodes no longer hold an element, so only the ones left in the stack are dropped
+        drop(linked_stack);
+        assert_eq!(rc.get(), 8);
+    }
+
+    #[test]
+    fn linked_stack_allocator() {
+        let alloc = TrackingAllocator::new();
+        let mut linked_stack = LinkedStack::new_in(&alloc);
+
+        linked_stack.extend(0..10);
+        assert_eq!(alloc.allocations(), 10);
+        assert_eq!(alloc.live(), 10);
+
+        assert_eq!(linked_stack.pop(), Some(9));
+        assert_eq!(linked_stack.remove(3), Some(5));
+        assert_eq!(alloc.live(), 8);
+
+        let mut bottom = linked_stack.split_off(4);
+        let clone = bottom.clone();
+        assert_eq!(alloc.live(), 12);
+
+        linked_stack.append(&mut bottom);
+        linked_stack.sort();
+        assert_eq!(
+            linked_stack.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 6, 7, 8]
+        );
+        assert_eq!(clone.iter().copied().collect::<Vec<_>>(), [3, 2, 1, 0]);
+        assert_eq!(alloc.live(), 12);
+
+        drop(clone);
+        drop(linked_stack);
+        drop(bottom);
+        assert_eq!(alloc.live(), 0);
+    }
+
+    #[test]
+    fn linked_stack_allocator_pool() {
+        let alloc = TrackingAllocator::new();
+        let mut linked_stack = LinkedStack::with_pool_capacity_in(4, &alloc);
+        let allocations = alloc.allocations();
+
+        for _ in 0..10 {
+            linked_stack.extend(0..4);
+            while linked_stack.pop().is_some() {}
+        }
+        // Releasing a node never allocates, so only the four nodes are
+        assert_eq!(alloc.allocations(), allocations + 4);
+        assert_eq!(linked_stack.pool_len(), 4);
+
+        drop(linked_stack);
+        assert_eq!(alloc.live(), 0);
+    }
+
+    #[test]
+    fn linked_stack_pool_extract_if() {
+        let alloc = TrackingAllocator::new();
+        let mut linked_stack = LinkedStack::with_pool_capacity_in(4, &alloc);
+        linked_stack.extend(0..8);
+
+        assert!(linked_stack.extract_if(|n| *n % 2 == 0).eq([6, 4, 2, 0]));
+        assert_eq!(linked_stack.pool_len(), 4);
+
+        // Extracted nodes are reused like popped ones
+        let allocations = alloc.allocations();
+        linked_stack.extend(0..4);
+        assert_eq!(alloc.allocations(), allocations);
+
+        drop(linked_stack);
+        assert_eq!(alloc.live(), 0);
+    }
+
+    #[test]
+    fn linked_stack_pool_capacity_is_a_limit() {
+        // Nothing is reserved up front, so a huge capacity only bounds how many nodes are kept
+        let mut linked_stack = LinkedStack::with_pool_capacity(1 << 40);
+        linked_stack.extend(0..10);
+        let clone = linked_stack.clone();
+
+        while linked_stack.pop().is_some() {}
+        assert_eq!(linked_stack.pool_len(), 10);
+        assert_eq!(clone.pool_len(), 0);
+        assert_eq!(clone.pool_capacity(), 1 << 40);
+    }
+
+    #[test]
+    fn linked_stack_checkpoint_rollback() {
+        let mut linked_stack = stack_of(0..5);
+
+        let mut checkpoint = linked_stack.checkpoint();
+        assert_eq!(checkpoint.pop(), Some(&4));
+        assert_eq!(checkpoint.pop(), Some(&3));
+        checkpoint.push(10);
+        assert_eq!(checkpoint.pop(), Some(&10));
+        checkpoint.push(11);
+        checkpoint.push(12);
+        assert_eq!(to_vec(&checkpoint), [12, 11, 2, 1, 0]);
+
+        checkpoint.rollback();
+        assert_eq!(to_vec(&linked_stack), [4, 3, 2, 1, 0]);
+        assert_eq!(linked_stack.len(), 5);
+        assert_eq!(linked_stack.pop(), Some(4));
+    }
+
+    #[test]
+    fn linked_stack_checkpoint_commit() {
+        let mut linked_stack = stack_of(0..5);
+
+        let mut checkpoint = linked_stack.checkpoint();
+        assert_eq!(checkpoint.pop(), Some(&4));
+        checkpoint.push(10);
+        checkpoint.commit();
+
+        assert_eq!(to_vec(&linked_stack), [10, 3, 2, 1, 0]);
+        assert_eq!(linked_stack.pop(), Some(10));
+    }
+
+    #[test]
+    fn linked_stack_checkpoint_empty() {
+        let mut linked_stack = LinkedStack::<i32>::new();
+
+        let mut checkpoint = linked_stack.checkpoint();
+        assert_eq!(checkpoint.pop(), None);
+        checkpoint.push(0);
+        assert_eq!(checkpoint.pop(), Some(&0));
+        assert_eq!(checkpoint.pop(), None);
+        checkpoint.rollback();
+
+        assert!(linked_stack.is_empty());
+    }
+
+    #[test]
+    fn linked_stack_checkpoint_nested() {
+        let mut linked_stack = stack_of(0..5);
+
+        let mut outer = linked_stack.checkpoint();
+        assert_eq!(outer.pop(), Some(&4));
+        outer.push(10);
+
+        let mut inner = outer.checkpoint();
+        assert_eq!(inner.pop(), Some(&10));
+        assert_eq!(inner.pop(), Some(&3));
+        inner.rollback();
+        assert_eq!(to_vec(&outer), [10, 3, 2, 1, 0]);
+
+        let mut inner = outer.checkpoint();
+        assert_eq!(inner.pop(), Some(&10));
+        inner.push(11);
+        inner.commit();
+        assert_eq!(to_vec(&outer), [11, 3, 2, 1, 0]);
+
+        // The inner commit is still undone by the outer rollback
+        outer.rollback();
+        assert_eq!(to_vec(&linked_stack), [4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn linked_stack_checkpoint_dropped() {
+        fn parse_pair(checkpoint: &mut Checkpoint<'_, i32>) -> Option<(i32, i32)> {
+            let a = *checkpoint.pop()?;
+            let b = *checkpoint.pop()?;
+            Some((a, b))
+        }
+
+        let mut linked_stack = stack_of(0..1);
+
+        // Bailing out early drops the checkpoint, which rolls it back
+        let mut checkpoint = linked_stack.checkpoint();
+        assert_eq!(parse_pair(&mut checkpoint), None);
+        checkpoint.push(10);
+        drop(checkpoint);
+        assert_eq!(to_vec(&linked_stack), [0]);
+
+        // The stack is usable as normal afterwards
+        linked_stack.push(1);
+        assert_eq!(linked_stack.pop(), Some(1));
+        assert_eq!(linked_stack.pop(), Some(0));
+        assert_eq!(linked_stack.pop(), None);
+    }
+
+    #[test]
+    fn linked_stack_checkpoint_leaked() {
+        let mut linked_stack = stack_of(0..5);
+        let rc = std::rc::Rc::default();
+
+        let mut checkpoint = linked_stack.checkpoint();
+        checkpoint.pop();
+        checkpoint.push(5);
+        std::mem::forget(checkpoint);
+
+        // The stack keeps the changes and pop is unaffected
+        assert_eq!(to_vec(&linked_stack), [5, 3, 2, 1, 0]);
+        assert_eq!(linked_stack.pop(), Some(5));
+
+        // Taking a new checkpoint does not undo the leaked changes
+        linked_stack.push(0);
+        let mut checkpoint = linked_stack.checkpoint();
+        checkpoint.pop();
+        checkpoint.rollback();
+        assert_eq!(to_vec(&linked_stack), [0, 3, 2, 1, 0]);
+
+        let mut linked_stack = LinkedStack::new();
+        linked_stack.push(DropCounter::new(&rc, 0));
+        let mut checkpoint = linked_stack.checkpoint();
+        checkpoint.pop();
+        std::mem::forget(checkpoint);
+        assert_eq!(rc.get(), 0);
+
+        // The leaked element is dropped by the next checkpoint
+        let _ = linked_stack.checkpoint();
+        assert_eq!(rc.get(), 1);
+    }
+
+    #[test]
+    fn linked_stack_checkpoint_separate_stacks() {
+        let mut a = stack_of(0..5);
+        let mut b = stack_of(0..5);
+
+        // Each checkpoint is tied to the stack it was taken from
+        let mut checkpoint_a = a.checkpoint();
+        let mut checkpoint_b = b.checkpoint();
+        checkpoint_a.pop();
+        checkpoint_b.pop();
+        checkpoint_b.pop();
+        checkpoint_a.commit();
+        checkpoint_b.rollback();
+
+        assert_eq!(to_vec(&a), [3, 2, 1, 0]);
+        assert_eq!(to_vec(&b), [4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn linked_stack_checkpoint_drop() {
+        let mut linked_stack = LinkedStack::new();
+        let rc = std::rc::Rc::default();
+
+        for n in 0..5 {
+            linked_stack.push(DropCounter::new(&rc, vec![n]));
+        }
+
+        // Popped elements are held until the checkpoint is resolved
+        let mut checkpoint = linked_stack.checkpoint();
+        checkpoint.pop();
+        checkpoint.pop();
+        assert_eq!(rc.get(), 0);
+
+        // Rolling back puts them back, only dropping what was pushed since
+        checkpoint.push(DropCounter::new(&rc, vec![10]));
+        checkpoint.rollback();
+        assert_eq!(rc.get(), 1);
+        assert_eq!(linked_stack.len(), 5);
+
+        // Committing an inner checkpoint keeps holding them for the outer one
+        let mut outer = linked_stack.checkpoint();
+        outer.pop();
+        let mut inner = outer.checkpoint();
+        inner.pop();
+        inner.commit();
+        assert_eq!(rc.get(), 1);
+
+        outer.commit();
+        assert_eq!(rc.get(), 3);
+        assert_eq!(linked_stack.len(), 3);
+
+        // Elements held by a leaked checkpoint are dropped with the stack
+        let mut checkpoint = linked_stack.checkpoint();
+        checkpoint.pop();
+        std::mem::forget(checkpoint);
+        drop(linked_stack);
+        assert_eq!(rc.get(), 6);
+    }
 }
@@ -0,0 +1,613 @@
+use crate::LinkedStack;
+
+/// A monoid used to aggregate the elements of a [`MonoidStack`] into an [`Output`](Self::Output),
+/// which does not have to be the element type: it can be a count or a total length, for example.
+///
+/// Each element is turned into an aggregate of its own with [`lift`](Self::lift), and aggregates
+/// are merged with [`combine`](Self::combine). `combine` must be associative, meaning
+/// `combine(a, combine(b, c)) == combine(combine(a, b), c)`, and [`identity`](Self::identity) must
+/// leave any aggregate unchanged when combined with it, on either side. `combine` does not have to
+/// be commutative: elements are always combined in the order they were pushed.
+pub trait Monoid<T> {
+    type Output;
+
+    /// Returns the aggregate of no elements.
+    fn identity() -> Self::Output;
+
+    /// Returns the aggregate of a single element.
+    fn lift(elem: &T) -> Self::Output;
+
+    fn combine(lhs: &Self::Output, rhs: &Self::Output) -> Self::Output;
+}
+
+/// Aggregates elements to their minimum, or [`None`] if there are none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Min;
+
+impl<T: Ord + Clone> Monoid<T> for Min {
+    type Output = Option<T>;
+
+    fn identity() -> Self::Output {
+        None
+    }
+
+    fn lift(elem: &T) -> Self::Output {
+        Some(elem.clone())
+    }
+
+    fn combine(lhs: &Self::Output, rhs: &Self::Output) -> Self::Output {
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => Some(std::cmp::min(lhs, rhs).clone()),
+            (aggregate, None) | (None, aggregate) => aggregate.clone(),
+        }
+    }
+}
+
+/// Aggregates elements to their maximum, or [`None`] if there are none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Max;
+
+impl<T: Ord + Clone> Monoid<T> for Max {
+    type Output = Option<T>;
+
+    fn identity() -> Self::Output {
+        None
+    }
+
+    fn lift(elem: &T) -> Self::Output {
+        Some(elem.clone())
+    }
+
+    fn combine(lhs: &Self::Output, rhs: &Self::Output) -> Self::Output {
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => Some(std::cmp::max(lhs, rhs).clone()),
+            (aggregate, None) | (None, aggregate) => aggregate.clone(),
+        }
+    }
+}
+
+/// Aggregates elements to their sum, which is zero if there are none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Sum;
+
+impl<T: std::ops::Add<Output = T> + std::iter::Sum + Clone> Monoid<T> for Sum {
+    type Output = T;
+
+    fn identity() -> Self::Output {
+        std::iter::empty().sum()
+    }
+
+    fn lift(elem: &T) -> Self::Output {
+        elem.clone()
+    }
+
+    fn combine(lhs: &Self::Output, rhs: &Self::Output) -> Self::Output {
+        lhs.clone() + rhs.clone()
+    }
+}
+
+/// A [`LinkedStack`] which keeps track of the aggregate of its elements under the [`Monoid`] `M`.
+///
+/// Each node stores its element alongside the aggregate of every element from the bottom of the
+/// stack up to and including it, so [`aggregate`](Self::aggregate) is O(1) no matter how the stack
+/// was pushed to or popped from.
+pub struct MonoidStack<T, M: Monoid<T>> {
+    /// Elements paired with the running aggregate up to them.
+    stack: LinkedStack<(T, M::Output)>,
+    /// Aggregate of the empty stack, kept around so that it can be returned by reference.
+    identity: M::Output,
+}
+
+impl<T: std::fmt::Debug, M: Monoid<T>> std::fmt::Debug for MonoidStack<T, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone, M: Monoid<T, Output: Clone>> Clone for MonoidStack<T, M> {
+    fn clone(&self) -> Self {
+        Self {
+            stack: self.stack.clone(),
+            identity: self.identity.clone(),
+        }
+    }
+}
+
+impl<T: PartialEq, M: Monoid<T>> PartialEq for MonoidStack<T, M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, M: Monoid<T>> Eq for MonoidStack<T, M> {}
+
+impl<T, M: Monoid<T>> Default for MonoidStack<T, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pushes each element in turn, so the last element yielded by the iterator ends up on top of the
+/// stack.
+impl<T, M: Monoid<T>> FromIterator<T> for MonoidStack<T, M> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stack = Self::new();
+        stack.extend(iter);
+        stack
+    }
+}
+
+impl<T, M: Monoid<T>> Extend<T> for MonoidStack<T, M> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T, M: Monoid<T>> MonoidStack<T, M> {
+    pub fn new() -> Self {
+        Self {
+            stack: LinkedStack::new(),
+            identity: M::identity(),
+        }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let aggregate = M::combine(self.aggregate(), &M::lift(&elem));
+        self.stack.push((elem, aggregate));
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.stack.pop().map(|(elem, _)| elem)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.stack.peek().map(|(elem, _)| elem)
+    }
+
+    /// Returns the aggregate of every element in the stack, from bottom to top, which is the
+    /// [`identity`](Monoid::identity) if the stack is empty.
+    pub fn aggregate(&self) -> &M::Output {
+        self.stack
+            .peek()
+            .map_or(&self.identity, |(_, aggregate)| aggregate)
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        while self.stack.pop().is_some() {}
+    }
+
+    /// Iterates over the elements of the stack from top to bottom.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.stack.iter().map(|(elem, _)| elem)
+    }
+}
+
+/// A FIFO queue which keeps track of the aggregate of its elements under the [`Monoid`] `M`, in
+/// amortized O(1).
+///
+/// This is the classic two-stack queue: elements are pushed onto a back stack, and moved over to a
+/// front stack all at once whenever the front runs out. Both stacks are [`MonoidStack`]s, so the
+/// aggregate of the whole queue is the aggregate of the front combined with the aggregate of the
+/// back. Unlike a running total, this works for any associative operation even if it cannot be
+/// undone when an element leaves the queue, such as min or max over a sliding window.
+pub struct MonoidQueue<T, M: Monoid<T>> {
+    /// Oldest elements, with the oldest on top. This combines its elements in the reverse order
+    /// they were pushed in, so that its aggregate goes from the front of the queue to the back.
+    front: MonoidStack<T, Rev<M>>,
+    /// Newest elements, with the newest on top.
+    back: MonoidStack<T, M>,
+}
+
+/// Combines elements in the opposite order as `M`.
+struct Rev<M>(std::marker::PhantomData<M>);
+
+impl<T, M: Monoid<T>> Monoid<T> for Rev<M> {
+    type Output = M::Output;
+
+    fn identity() -> Self::Output {
+        M::identity()
+    }
+
+    fn lift(elem: &T) -> Self::Output {
+        M::lift(elem)
+    }
+
+    fn combine(lhs: &Self::Output, rhs: &Self::Output) -> Self::Output {
+        M::combine(rhs, lhs)
+    }
+}
+
+impl<T: std::fmt::Debug, M: Monoid<T>> std::fmt::Debug for MonoidQueue<T, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let back = self.back.iter().collect::<Vec<_>>();
+        f.debug_list()
+            .entries(self.front.iter())
+            .entries(back.into_iter().rev())
+            .finish()
+    }
+}
+
+impl<T: Clone, M: Monoid<T, Output: Clone>> Clone for MonoidQueue<T, M> {
+    fn clone(&self) -> Self {
+        Self {
+            front: self.front.clone(),
+            back: self.back.clone(),
+        }
+    }
+}
+
+impl<T, M: Monoid<T>> Default for MonoidQueue<T, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, M: Monoid<T>> FromIterator<T> for MonoidQueue<T, M> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut queue = Self::new();
+        queue.extend(iter);
+        queue
+    }
+}
+
+impl<T, M: Monoid<T>> Extend<T> for MonoidQueue<T, M> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T, M: Monoid<T>> MonoidQueue<T, M> {
+    pub fn new() -> Self {
+        Self {
+            front: MonoidStack::new(),
+            back: MonoidStack::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty() && self.back.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.front.clear();
+        self.back.clear();
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.back.push(elem);
+    }
+
+    /// Removes the oldest element of the queue.
+    ///
+    /// This is O(1) amortized: when the front stack is empty, every element of the back stack is
+    /// moved over to it, but each element is only ever moved once.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.front.is_empty() {
+            while let Some(elem) = self.back.pop() {
+                self.front.push(elem);
+            }
+        }
+
+        self.front.pop()
+    }
+
+    /// Returns the aggregate of every element in the queue, from front to back, which is the
+    /// [`identity`](Monoid::identity) if the queue is empty.
+    pub fn aggregate(&self) -> M::Output {
+        M::combine(self.front.aggregate(), self.back.aggregate())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dsa_util::DropCounter;
+
+    use super::*;
+
+    struct Gcd;
+
+    impl Monoid<u64> for Gcd {
+        type Output = u64;
+
+        fn identity() -> u64 {
+            0
+        }
+
+        fn lift(elem: &u64) -> u64 {
+            *elem
+        }
+
+        fn combine(lhs: &u64, rhs: &u64) -> u64 {
+            let (mut a, mut b) = (*lhs, *rhs);
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a
+        }
+    }
+
+    /// Concatenation is associative but not commutative, which catches combining out of order.
+    struct Concat;
+
+    impl Monoid<String> for Concat {
+        type Output = String;
+
+        fn identity() -> String {
+            String::new()
+        }
+
+        fn lift(elem: &String) -> String {
+            elem.clone()
+        }
+
+        fn combine(lhs: &String, rhs: &String) -> String {
+            format!("{lhs}{rhs}")
+        }
+    }
+
+    /// Aggregates to a different type than the elements.
+    struct TotalLen;
+
+    impl Monoid<String> for TotalLen {
+        type Output = usize;
+
+        fn identity() -> usize {
+            0
+        }
+
+        fn lift(elem: &String) -> usize {
+            elem.len()
+        }
+
+        fn combine(lhs: &usize, rhs: &usize) -> usize {
+            lhs + rhs
+        }
+    }
+
+    #[test]
+    fn monoid_stack_min_max() {
+        let mut min = MonoidStack::<i32, Min>::new();
+        let mut max = MonoidStack::<i32, Max>::new();
+        assert_eq!(min.aggregate(), &None);
+        assert_eq!(max.aggregate(), &None);
+
+        for n in [5, 3, 8, 1, 9, 2] {
+            min.push(n);
+            max.push(n);
+        }
+        assert_eq!(min.aggregate(), &Some(1));
+        assert_eq!(max.aggregate(), &Some(9));
+
+        let expected = [
+            (2, 1, 9),
+            (9, 1, 9),
+            (1, 1, 8),
+            (8, 3, 8),
+            (3, 3, 5),
+            (5, 5, 5),
+        ];
+        for (elem, min_below, max_below) in expected {
+            assert_eq!(min.aggregate(), &Some(min_below));
+            assert_eq!(max.aggregate(), &Some(max_below));
+            assert_eq!(min.pop(), Some(elem));
+            assert_eq!(max.pop(), Some(elem));
+        }
+
+        assert_eq!(min.aggregate(), &None);
+        assert_eq!(max.aggregate(), &None);
+    }
+
+    #[test]
+    fn monoid_stack_sum() {
+        let mut stack = (1..=10).collect::<MonoidStack<u32, Sum>>();
+        assert_eq!(stack.len(), 10);
+        assert_eq!(stack.aggregate(), &55);
+
+        assert_eq!(stack.pop(), Some(10));
+        assert_eq!(stack.aggregate(), &45);
+
+        stack.push(100);
+        assert_eq!(stack.peek(), Some(&100));
+        assert_eq!(stack.aggregate(), &145);
+
+        stack.clear();
+        assert!(stack.is_empty());
+        assert_eq!(stack.aggregate(), &0);
+    }
+
+    #[test]
+    fn monoid_stack_gcd() {
+        let mut stack = MonoidStack::<u64, Gcd>::new();
+
+        stack.push(84);
+        assert_eq!(stack.aggregate(), &84);
+        stack.push(36);
+        assert_eq!(stack.aggregate(), &12);
+        stack.push(20);
+        assert_eq!(stack.aggregate(), &4);
+
+        stack.pop();
+        assert_eq!(stack.aggregate(), &12);
+    }
+
+    #[test]
+    fn monoid_stack_order() {
+        let mut stack = MonoidStack::<String, Concat>::new();
+
+        for s in ["a", "b", "c"] {
+            stack.push(s.to_string());
+        }
+        assert_eq!(stack.aggregate(), "abc");
+        assert_eq!(stack.iter().cloned().collect::<Vec<_>>(), ["c", "b", "a"]);
+        assert_eq!(format!("{stack:?}"), r#"["c", "b", "a"]"#);
+
+        stack.pop();
+        assert_eq!(stack.aggregate(), "ab");
+    }
+
+    #[test]
+    fn monoid_stack_output() {
+        let mut stack = MonoidStack::<String, TotalLen>::new();
+        assert_eq!(stack.aggregate(), &0);
+
+        for s in ["a", "bcd", "ef"] {
+            stack.push(s.to_string());
+        }
+        assert_eq!(stack.aggregate(), &6);
+
+        stack.pop();
+        assert_eq!(stack.aggregate(), &4);
+    }
+
+    #[test]
+    fn monoid_stack_clone_eq() {
+        let stack = (0..10).collect::<MonoidStack<i32, Max>>();
+        let mut clone = stack.clone();
+        assert_eq!(clone, stack);
+
+        clone.pop();
+        assert_ne!(clone, stack);
+        assert_eq!(clone.aggregate(), &Some(8));
+        assert_eq!(stack.aggregate(), &Some(9));
+    }
+
+    /// Keeps the bottom element, sharing it with every aggregate above it.
+    struct Bottom;
+
+    impl<T> Monoid<std::rc::Rc<T>> for Bottom {
+        type Output = Option<std::rc::Rc<T>>;
+
+        fn identity() -> Self::Output {
+            None
+        }
+
+        fn lift(elem: &std::rc::Rc<T>) -> Self::Output {
+            Some(std::rc::Rc::clone(elem))
+        }
+
+        fn combine(lhs: &Self::Output, rhs: &Self::Output) -> Self::Output {
+            lhs.as_ref().or(rhs.as_ref()).cloned()
+        }
+    }
+
+    #[test]
+    fn monoid_stack_drop() {
+        let rc = std::rc::Rc::default();
+        let mut stack = MonoidStack::<_, Bottom>::new();
+
+        for n in 0..10 {
+            stack.push(std::rc::Rc::new(DropCounter::new(&rc, n)));
+        }
+        // The bottom element is shared with its own aggregate and every aggregate above it
+        let bottom = std::rc::Rc::clone(stack.aggregate().as_ref().unwrap());
+        assert_eq!(std::rc::Rc::strong_count(&bottom), 12);
+
+        drop(stack.pop());
+        assert_eq!(rc.get(), 1);
+        assert_eq!(std::rc::Rc::strong_count(&bottom), 11);
+
+        drop(stack);
+        assert_eq!(rc.get(), 9);
+        assert_eq!(std::rc::Rc::strong_count(&bottom), 1);
+
+        drop(bottom);
+        assert_eq!(rc.get(), 10);
+    }
+
+    #[test]
+    fn monoid_queue_push_pop() {
+        let mut queue = MonoidQueue::<i32, Sum>::new();
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop_front(), None);
+        assert_eq!(queue.aggregate(), 0);
+
+        queue.extend(0..5);
+        assert_eq!(queue.pop_front(), Some(0));
+        queue.extend(5..10);
+        assert_eq!(queue.len(), 9);
+        assert_eq!(format!("{queue:?}"), "[1, 2, 3, 4, 5, 6, 7, 8, 9]");
+
+        for n in 1..10 {
+            assert_eq!(queue.aggregate(), (n..10).sum());
+            assert_eq!(queue.pop_front(), Some(n));
+        }
+        assert_eq!(queue.pop_front(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn monoid_queue_order() {
+        let mut queue = MonoidQueue::<String, Concat>::new();
+
+        for s in ["a", "b", "c"] {
+            queue.push_back(s.to_string());
+        }
+        assert_eq!(queue.aggregate(), "abc");
+
+        // Splits the contents across both stacks
+        assert_eq!(queue.pop_front().as_deref(), Some("a"));
+        queue.push_back("d".to_string());
+        assert_eq!(queue.aggregate(), "bcd");
+        assert_eq!(format!("{queue:?}"), r#"["b", "c", "d"]"#);
+
+        let clone = queue.clone();
+        queue.clear();
+        assert_eq!(queue.aggregate(), "");
+        assert_eq!(clone.aggregate(), "bcd");
+    }
+
+    #[test]
+    fn monoid_queue_sliding_window() {
+        let data = [4, 2, 12, 3, 8, 1, 7, 7, 0, 5, 9, 6, 2, 11, 3];
+        let window = 4;
+
+        let mut max = MonoidQueue::<i32, Max>::new();
+        let mut min = MonoidQueue::<i32, Min>::new();
+
+        for (i, n) in data.into_iter().enumerate() {
+            max.push_back(n);
+            min.push_back(n);
+            if max.len() > window {
+                max.pop_front();
+                min.pop_front();
+            }
+
+            let start = (i + 1).saturating_sub(window);
+            let slice = &data[start..=i];
+            assert_eq!(max.aggregate(), slice.iter().max().copied());
+            assert_eq!(min.aggregate(), slice.iter().min().copied());
+        }
+    }
+
+    #[test]
+    fn monoid_queue_drop() {
+        let rc = std::rc::Rc::default();
+        let mut queue = MonoidQueue::<_, Bottom>::new();
+
+        for n in 0..10 {
+            queue.push_back(std::rc::Rc::new(DropCounter::new(&rc, n)));
+        }
+        drop(queue.pop_front());
+        queue.push_back(std::rc::Rc::new(DropCounter::new(&rc, 10)));
+        assert_eq!(rc.get(), 1);
+
+        drop(queue);
+        assert_eq!(rc.get(), 11);
+    }
+}
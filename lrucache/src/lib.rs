@@ -0,0 +1,396 @@
+use dsa_linkeddeque::{LinkedDeque, NodeHandle};
+
+/// A least-recently-used cache holding at most `capacity` entries.
+///
+/// Entries are kept in a [`LinkedDeque`] ordered from most to least recently used, and a
+/// [`HashMap`] maps each key to the [`NodeHandle`] of its entry. Looking up an entry can then move
+/// it to the front of the deque, and evicting the least recently used entry pops it from the back,
+/// both in O(1).
+///
+/// [`HashMap`]: std::collections::HashMap
+pub struct LruCache<K, V> {
+    map: std::collections::HashMap<K, NodeHandle>,
+    order: LinkedDeque<(K, V)>,
+    capacity: usize,
+    on_evict: Option<Box<dyn FnMut(K, V)>>,
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for LruCache<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.order.iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
+
+impl<K: std::hash::Hash + Eq + Clone, V> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "Cannot create a LruCache with a capacity of 0"
+        );
+        Self {
+            map: std::collections::HashMap::with_capacity(capacity),
+            order: LinkedDeque::new(),
+            capacity,
+            on_evict: None,
+        }
+    }
+
+    /// Registers a callback which is handed every entry the cache evicts to make room, either in
+    /// [`put`] or [`resize`]. Entries removed explicitly through [`pop_lru`] or [`remove`] are
+    /// returned to the caller instead.
+    ///
+    /// [`put`]: Self::put
+    /// [`resize`]: Self::resize
+    /// [`pop_lru`]: Self::pop_lru
+    /// [`remove`]: Self::remove
+    pub fn set_on_evict(&mut self, on_evict: impl FnMut(K, V) + 'static) {
+        self.on_evict = Some(Box::new(on_evict));
+    }
+
+    /// Returns the value for `key` and marks it as the most recently used entry.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    /// Returns the value for `key` and marks it as the most recently used entry.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        self.order.move_to_front(handle).map(|(_, v)| v)
+    }
+
+    /// Returns the value for `key` without changing how recently it was used.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        self.order.get(handle).map(|(_, v)| v)
+    }
+
+    /// Returns the least recently used entry without changing how recently it was used.
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.order.peek_back().map(|(k, v)| (k, v))
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Inserts `value` for `key` as the most recently used entry, evicting the least recently used
+    /// entry if the cache is full. If `key` was already in the cache, its previous value is
+    /// returned and nothing is evicted.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&handle) = self.map.get(&key) {
+            return self
+                .order
+                .move_to_front(handle)
+                .map(|(_, v)| std::mem::replace(v, value));
+        }
+
+        if self.map.len() == self.capacity {
+            self.evict();
+        }
+
+        let handle = self.order.push_front_handle((key.clone(), value));
+        self.map.insert(key, handle);
+        None
+    }
+
+    /// Removes and returns the least recently used entry.
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        self.order.pop_back().map(|(k, v)| {
+            self.map.remove(&k);
+            (k, v)
+        })
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + Eq + ?Sized,
+    {
+        let handle = self.map.remove(key)?;
+        self.order.remove(handle).map(|(_, v)| v)
+    }
+
+    /// Changes the capacity of the cache, evicting least recently used entries until it fits.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is 0.
+    pub fn resize(&mut self, capacity: usize) {
+        assert!(capacity > 0, "Cannot resize a LruCache to a capacity of 0");
+
+        while self.map.len() > capacity {
+            self.evict();
+        }
+        self.capacity = capacity;
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.order = LinkedDeque::new();
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Iterates over the entries of the cache from most to least recently used, without changing
+    /// how recently they were used. Use `.rev()` to go from least to most recently used instead.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator {
+        self.order.iter().map(|(k, v)| (k, v))
+    }
+
+    /// Like [`iter`](Self::iter), but with mutable access to the values.
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (&K, &mut V)> + ExactSizeIterator {
+        self.order.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    fn evict(&mut self) {
+        if let Some((k, v)) = self.pop_lru()
+            && let Some(on_evict) = self.on_evict.as_mut()
+        {
+            on_evict(k, v);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dsa_util::DropCounter;
+
+    use super::*;
+
+    #[test]
+    fn lru_cache_new() {
+        let cache = LruCache::<i32, i32>::new(10);
+        assert_eq!(cache.len(), 0);
+        assert_eq!(cache.capacity(), 10);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn lru_cache_new_panic_zero_capacity() {
+        let _ = LruCache::<i32, i32>::new(0);
+    }
+
+    #[test]
+    fn lru_cache_put_get() {
+        let mut cache = LruCache::new(3);
+        assert_eq!(cache.put("a", 0), None);
+        assert_eq!(cache.put("b", 1), None);
+        assert_eq!(cache.put("c", 2), None);
+
+        assert_eq!(cache.get("a"), Some(&0));
+        assert_eq!(cache.get("b"), Some(&1));
+        assert_eq!(cache.get("c"), Some(&2));
+        assert_eq!(cache.get("d"), None);
+        assert_eq!(cache.len(), 3);
+
+        // Overwriting a key returns its old value without evicting anything
+        assert_eq!(cache.put("a", 10), Some(0));
+        assert_eq!(cache.get("a"), Some(&10));
+        assert_eq!(cache.len(), 3);
+
+        *cache.get_mut("b").unwrap() += 10;
+        assert_eq!(cache.peek("b"), Some(&11));
+    }
+
+    #[test]
+    fn lru_cache_evict() {
+        let mut cache = LruCache::new(3);
+        cache.put("a", 0);
+        cache.put("b", 1);
+        cache.put("c", 2);
+
+        // "a" becomes the most recently used, so "b" is evicted first
+        cache.get("a");
+        cache.put("d", 3);
+        assert!(!cache.contains("b"));
+        assert_eq!(cache.len(), 3);
+
+        cache.put("e", 4);
+        assert!(!cache.contains("c"));
+        assert!(cache.contains("a"));
+        assert!(cache.contains("d"));
+        assert!(cache.contains("e"));
+    }
+
+    #[test]
+    fn lru_cache_peek() {
+        let mut cache = LruCache::new(2);
+        cache.put("a", 0);
+        cache.put("b", 1);
+
+        // Peeking does not count as using the entry
+        assert_eq!(cache.peek("a"), Some(&0));
+        assert_eq!(cache.peek_lru(), Some((&"a", &0)));
+        cache.put("c", 2);
+        assert_eq!(cache.peek("a"), None);
+        assert_eq!(cache.peek_lru(), Some((&"b", &1)));
+    }
+
+    #[test]
+    fn lru_cache_pop_lru() {
+        let mut cache = LruCache::new(3);
+        assert_eq!(cache.pop_lru(), None);
+
+        cache.put("a", 0);
+        cache.put("b", 1);
+        cache.put("c", 2);
+        cache.get("a");
+
+        assert_eq!(cache.pop_lru(), Some(("b", 1)));
+        assert_eq!(cache.pop_lru(), Some(("c", 2)));
+        assert_eq!(cache.pop_lru(), Some(("a", 0)));
+        assert_eq!(cache.pop_lru(), None);
+        assert!(cache.is_empty());
+        assert!(!cache.contains("a"));
+    }
+
+    #[test]
+    fn lru_cache_remove() {
+        let mut cache = LruCache::new(3);
+        cache.put("a".to_string(), 0);
+        cache.put("b".to_string(), 1);
+
+        assert_eq!(cache.remove("a"), Some(0));
+        assert_eq!(cache.remove("a"), None);
+        assert_eq!(cache.len(), 1);
+
+        cache.put("c".to_string(), 2);
+        cache.put("d".to_string(), 3);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.pop_lru(), Some(("b".to_string(), 1)));
+    }
+
+    #[test]
+    fn lru_cache_resize() {
+        let mut cache = LruCache::new(5);
+        for n in 0..5 {
+            cache.put(n, n);
+        }
+
+        cache.resize(2);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.capacity(), 2);
+        assert!(cache.contains(&3));
+        assert!(cache.contains(&4));
+
+        cache.resize(3);
+        cache.put(5, 5);
+        assert_eq!(cache.len(), 3);
+        assert!(cache.contains(&3));
+    }
+
+    #[test]
+    fn lru_cache_on_evict() {
+        let evicted = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut cache = LruCache::new(2);
+
+        let evicted_clone = std::rc::Rc::clone(&evicted);
+        cache.set_on_evict(move |k, v| evicted_clone.borrow_mut().push((k, v)));
+
+        cache.put("a", 0);
+        cache.put("b", 1);
+        cache.put("a", 2);
+        assert!(evicted.borrow().is_empty());
+
+        cache.put("c", 3);
+        assert_eq!(*evicted.borrow(), [("b", 1)]);
+
+        cache.resize(1);
+        assert_eq!(*evicted.borrow(), [("b", 1), ("a", 2)]);
+
+        // Entries removed explicitly are not reported as evicted
+        cache.pop_lru();
+        assert_eq!(evicted.borrow().len(), 2);
+    }
+
+    #[test]
+    fn lru_cache_iter() {
+        let mut cache = LruCache::new(5);
+        for n in 0..5 {
+            cache.put(n, n * 10);
+        }
+        cache.get(&1);
+
+        assert_eq!(
+            cache.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            [1, 4, 3, 2, 0]
+        );
+        assert_eq!(
+            cache.iter().rev().map(|(k, _)| *k).collect::<Vec<_>>(),
+            [0, 2, 3, 4, 1]
+        );
+        assert_eq!(cache.iter().len(), 5);
+
+        for (_, v) in cache.iter_mut() {
+            *v += 1;
+        }
+        assert_eq!(cache.peek(&0), Some(&1));
+
+        // Iterating does not count as using the entries
+        cache.put(5, 50);
+        assert!(!cache.contains(&0));
+
+        assert_eq!(&format!("{cache:?}"), "{5: 50, 1: 11, 4: 41, 3: 31, 2: 21}");
+    }
+
+    #[test]
+    fn lru_cache_clear() {
+        let mut cache = LruCache::new(2);
+        cache.put(0, 0);
+        cache.put(1, 1);
+        cache.clear();
+
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&0), None);
+        cache.put(2, 2);
+        assert_eq!(cache.get(&2), Some(&2));
+    }
+
+    #[test]
+    fn lru_cache_drop() {
+        let rc = std::rc::Rc::default();
+        let mut cache = LruCache::new(5);
+
+        for n in 0..10 {
+            cache.put(n, DropCounter::new(&rc, vec![n]));
+        }
+        assert_eq!(rc.get(), 5);
+
+        drop(cache.put(9, DropCounter::new(&rc, vec![9])));
+        assert_eq!(rc.get(), 6);
+
+        drop(cache);
+        assert_eq!(rc.get(), 11);
+    }
+}
@@ -22,3 +22,100 @@ impl<T> DropCounter<T> {
         self.counter.get()
     }
 }
+
+/// Global allocator which counts the allocations made by each thread, so that tests can check how
+/// many allocations an operation performs. Register it in a test binary with
+/// `#[global_allocator]`, then read the count with [`CountingAllocator::allocations`].
+///
+/// Counts are kept per thread since tests run concurrently.
+pub struct CountingAllocator;
+
+std::thread_local! {
+    static ALLOCATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { std::alloc::System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        unsafe { std::alloc::System.dealloc(ptr, layout) }
+    }
+}
+
+impl CountingAllocator {
+    /// Returns the number of allocations made so far by the current thread.
+    pub fn allocations() -> usize {
+        ALLOCATIONS.with(|count| count.get())
+    }
+}
+
+/// Allocator which forwards to the global allocator while keeping track of how many allocations
+/// are live, so that tests can check containers route their nodes through a custom allocator and
+/// return all of them. Pass it by reference, as `&TrackingAllocator` is itself an allocator.
+#[derive(Default)]
+pub struct TrackingAllocator {
+    allocations: std::cell::Cell<usize>,
+    live: std::cell::Cell<usize>,
+}
+
+unsafe impl allocator_api2::alloc::Allocator for TrackingAllocator {
+    fn allocate(
+        &self,
+        layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, allocator_api2::alloc::AllocError> {
+        let ptr = allocator_api2::alloc::Global.allocate(layout)?;
+        self.allocations.set(self.allocations.get() + 1);
+        self.live.set(self.live.get() + 1);
+        Ok(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+        self.live.set(self.live.get() - 1);
+        unsafe { allocator_api2::alloc::Global.deallocate(ptr, layout) }
+    }
+}
+
+impl TrackingAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the total number of allocations made so far.
+    pub fn allocations(&self) -> usize {
+        self.allocations.get()
+    }
+
+    /// Returns the number of allocations which have not been deallocated yet.
+    pub fn live(&self) -> usize {
+        self.live.get()
+    }
+}
+
+/// Thread-safe version of [`DropCounter`], for tests which drop values from several threads.
+pub struct SyncDropCounter<T> {
+    _val: T,
+    counter: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl<T> Drop for SyncDropCounter<T> {
+    fn drop(&mut self) {
+        self.counter
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+impl<T> SyncDropCounter<T> {
+    pub fn new(counter: &std::sync::Arc<std::sync::atomic::AtomicUsize>, val: T) -> Self {
+        Self {
+            _val: val,
+            counter: std::sync::Arc::clone(counter),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.counter.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}